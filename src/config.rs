@@ -0,0 +1,1183 @@
+//! Generation of the workspace's launch configurations, tasks, extension recommendations and
+//! settings from discovered runnables.
+
+use clap::ValueEnum;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::discovery::{Runnable, RunnableType, TestTarget};
+use crate::{jsonc, Error};
+
+/// Settings that shape every generated launch configuration.
+pub struct LaunchOptions {
+    pub debugger: Debugger,
+    /// Overrides the `type` the debugger would normally use
+    pub config_type: Option<String>,
+    /// GDB executable for cppdbg configurations; `None` leaves it to PATH
+    pub gdb_path: Option<String>,
+    pub env: BTreeMap<String, String>,
+    /// Variables set unless the package's metadata or `env` sets them too
+    pub default_env: BTreeMap<String, String>,
+    pub pre_launch_build: bool,
+    /// Append each runnable's required features to its configuration names
+    pub name_features: bool,
+    /// Keep configuration names exactly as built, without normalizing their whitespace
+    pub raw_names: bool,
+    /// Have CodeLLDB launch the built binary or example directly instead of building it through
+    /// its `cargo` block
+    pub program_mode: bool,
+    pub profiles: Vec<Profile>,
+    /// Target triple passed to cargo; `None` builds for the host
+    pub target: Option<String>,
+    pub compound_all: bool,
+    /// Program arguments per runnable name, as read from --args-file
+    pub program_args: BTreeMap<String, Vec<String>>,
+    /// Features enabled for every runnable, in addition to its required features
+    pub features: Vec<String>,
+    pub no_default_features: bool,
+    /// Break at the program entry point when a session starts
+    pub stop_at_entry: bool,
+    /// CodeLLDB `terminal`; `None` leaves the extension's default
+    pub terminal: Option<Terminal>,
+    /// Absolute path of a dotenv file every configuration loads
+    pub env_file: Option<PathBuf>,
+    /// Passed through to CodeLLDB configurations
+    pub init_commands: Vec<String>,
+    pub source_map: BTreeMap<String, String>,
+    /// Also emit an attach-to-process configuration for every binary
+    pub with_attach: bool,
+    /// Debug server binaries are attached to on a remote device
+    pub remote: Option<RemoteTarget>,
+    /// Replaces the built-in configuration shape
+    pub template: Option<ConfigurationTemplate>,
+}
+
+/// Address of a debug server (`lldb-server` or `gdbserver`) running on a remote device.
+pub struct RemoteTarget {
+    pub host: String,
+    pub port: u16,
+}
+
+/// A user-supplied launch configuration, rendered once per runnable.
+///
+/// The template is the JSON (comments allowed) of a single configuration in which `{{name}}`,
+/// `{{package}}`, `{{bin}}`, `{{kind}}`, `{{cwd}}`, `{{manifest_path}}` and `{{features}}` are
+/// replaced by the runnable's values. Values are escaped for use inside JSON strings.
+pub struct ConfigurationTemplate {
+    text: String,
+}
+
+impl ConfigurationTemplate {
+    /// Placeholders a template may use.
+    pub const PLACEHOLDERS: &[&str] = &["name", "package", "bin", "kind", "cwd", "manifest_path", "features"];
+
+    /// Checks that `text` only uses known placeholders and renders to a launch configuration.
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let template = Self { text: text.to_string() };
+        let samples: Vec<(&str, String)> = Self::PLACEHOLDERS.iter().map(|p| (*p, p.to_string())).collect();
+        template
+            .render(&samples)
+            .map_err(|message| Error::InvalidTemplate { message })?;
+        Ok(template)
+    }
+
+    fn render(&self, values: &[(&str, String)]) -> Result<Configuration, String> {
+        let mut rendered = String::new();
+        let mut rest = self.text.as_str();
+        while let Some(start) = rest.find("{{") {
+            let end = rest[start..].find("}}").ok_or("unclosed '{{'")? + start;
+            let placeholder = rest[start + 2..end].trim();
+            let value = values
+                .iter()
+                .find(|(name, _)| *name == placeholder)
+                .map(|(_, value)| value)
+                .ok_or_else(|| format!("unknown placeholder '{{{{{}}}}}'", placeholder))?;
+            let escaped = serde_json::to_string(value).map_err(|e| e.to_string())?;
+            rendered.push_str(&rest[..start]);
+            rendered.push_str(&escaped[1..escaped.len() - 1]);
+            rest = &rest[end + 2..];
+        }
+        rendered.push_str(rest);
+
+        let value = jsonc::parse(&rendered).map_err(|e| e.to_string())?;
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
+}
+
+/// Cargo build profile a launch configuration debugs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Profile {
+    /// The default `dev` profile
+    #[value(alias = "debug")]
+    Dev,
+    /// The optimized `release` profile
+    Release,
+}
+
+impl Profile {
+    /// Suffix that keeps configuration names unique; dev configurations only need one
+    /// when they sit alongside other profiles.
+    fn name_suffix(self, multiple_profiles: bool) -> Option<&'static str> {
+        match self {
+            Profile::Dev if multiple_profiles => Some("dev"),
+            Profile::Dev => None,
+            Profile::Release => Some("release"),
+        }
+    }
+
+    /// Directory under the cargo target directory that holds this profile's artifacts.
+    fn target_subdirectory(self) -> &'static str {
+        match self {
+            Profile::Dev => "debug",
+            Profile::Release => "release",
+        }
+    }
+}
+
+/// Debug adapter the launch configurations are written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Debugger {
+    /// CodeLLDB (`"type": "lldb"`), building through its `cargo` block
+    #[value(alias = "codelldb")]
+    Lldb,
+    /// GDB through the C/C++ extension (`"type": "cppdbg"`), launching the built binary
+    Gdb,
+    /// The Visual Studio debugger through the C/C++ extension (`"type": "cppvsdbg"`), for MSVC toolchains
+    Cppvsdbg,
+    /// LLVM's own adapter through the LLDB DAP extension (`"type": "lldb-dap"`), launching the built binary
+    LldbDap,
+}
+
+/// Launch configurations for a set of runnables, as produced by [`generate_launch_config`].
+#[derive(Serialize, Deserialize)]
+pub struct LaunchConfig {
+    pub version: String,
+    pub configurations: Vec<Configuration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compounds: Option<Vec<Compound>>,
+}
+
+/// A launch entry that starts several configurations together.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Compound {
+    pub name: String,
+    pub configurations: Vec<String>,
+}
+
+/// One entry of the `launch.configurations` array.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Configuration {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub config_type: String,
+    pub request: String,
+    pub cwd: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cargo: Option<CargoConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub program: Option<String>,
+    #[serde(rename = "MIMode", skip_serializing_if = "Option::is_none")]
+    pub mi_mode: Option<String>,
+    #[serde(rename = "miDebuggerPath", skip_serializing_if = "Option::is_none")]
+    pub mi_debugger_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Vec<EnvironmentEntry>>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(rename = "preLaunchTask", skip_serializing_if = "Option::is_none")]
+    pub pre_launch_task: Option<String>,
+    /// CodeLLDB's (and lldb-dap's) name for breaking at the program entry point
+    #[serde(rename = "stopOnEntry", skip_serializing_if = "Option::is_none")]
+    pub stop_on_entry: Option<bool>,
+    /// The C/C++ extension's (cppdbg and cppvsdbg) name for the same setting
+    #[serde(rename = "stopAtEntry", skip_serializing_if = "Option::is_none")]
+    pub stop_at_entry: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminal: Option<Terminal>,
+    #[serde(rename = "envFile", skip_serializing_if = "Option::is_none")]
+    pub env_file: Option<String>,
+    /// LLDB commands CodeLLDB or lldb-dap runs before the debug target is created
+    #[serde(rename = "initCommands", default, skip_serializing_if = "Vec::is_empty")]
+    pub init_commands: Vec<String>,
+    /// LLDB source path remapping, from build-time path to local path
+    #[serde(rename = "sourceMap", default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub source_map: BTreeMap<String, String>,
+    /// Process CodeLLDB or lldb-dap attaches to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<String>,
+    /// The C/C++ extension's name for the same setting
+    #[serde(rename = "processId", skip_serializing_if = "Option::is_none")]
+    pub process_id: Option<String>,
+    /// Keys this tool doesn't model, e.g. from a `--template`
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Where CodeLLDB connects the debuggee's standard input and output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Terminal {
+    /// VS Code's integrated terminal
+    Integrated,
+    /// A separate terminal window
+    External,
+    /// The debug console
+    Console,
+}
+
+/// cppdbg takes its environment as a list of name/value pairs rather than a map.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EnvironmentEntry {
+    pub name: String,
+    pub value: String,
+}
+
+/// CodeLLDB's `cargo` block: the cargo command that builds the program to debug.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CargoConfig {
+    pub args: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<CargoFilter>,
+}
+
+/// Selects which artifact CodeLLDB launches when the cargo command builds more than one.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CargoFilter {
+    pub name: String,
+    pub kind: String,
+}
+
+/// The `launch` section of a workspace file.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WorkspaceLaunchConfig {
+    pub version: String,
+    pub configurations: Vec<Configuration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compounds: Option<Vec<Compound>>,
+}
+
+/// Workspace file sections produced by the generators, ready to be merged into the file on disk.
+pub struct GeneratedSections {
+    pub launch: WorkspaceLaunchConfig,
+    pub tasks: serde_json::Value,
+    pub extensions: serde_json::Value,
+    pub settings: serde_json::Value,
+}
+
+/// Generates launch configurations for `runnables`, with paths relative to `root_dir`.
+pub fn generate_launch_config(runnables: &[Runnable], root_dir: &Path, options: &LaunchOptions) -> LaunchConfig {
+    let mut configurations = Vec::new();
+    // Project each configuration was generated for, parallel to `configurations`
+    let mut origins: Vec<&Path> = Vec::new();
+    // Positions of the configurations the "Run all binaries" compound starts
+    let mut compound_members = Vec::new();
+    // Test and bench configurations left out because the debugger can only launch a fixed binary
+    let mut skipped_without_program = 0;
+
+    // Point at the env file through ${workspaceFolder} so the workspace stays relocatable
+    let env_file = options.env_file.as_ref().map(|path| {
+        let root = std::path::absolute(root_dir).unwrap_or_else(|_| root_dir.to_path_buf());
+        workspace_relative_path(path, &root)
+    });
+    
+    // Order by package, then kind, then target so regenerating an unchanged tree yields identical output
+    let mut ordered: Vec<&Runnable> = runnables.iter().collect();
+    ordered.sort_by(|a, b| {
+        a.package.cmp(&b.package)
+            .then_with(|| a.runnable_type.cmp(&b.runnable_type))
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.project_path.cmp(&b.project_path))
+    });
+
+    for runnable in ordered {
+        let package_dir = workspace_relative_path(&runnable.package_path, root_dir);
+        
+        // Run from the package's own directory so nested workspace members find their assets,
+        // unless the package's metadata names another directory
+        let cwd = match &runnable.overrides.cwd {
+            Some(dir) => workspace_relative_path(&runnable.package_path.join(dir), root_dir),
+            None => package_dir.clone(),
+        };
+        
+        // Generate manifest path argument for cargo
+        let manifest_path_arg = format!("--manifest-path={}/Cargo.toml", package_dir);
+        
+        // Work out the type-specific name, cargo invocation and artifact filter
+        let (name, cargo_args, filter, program_args) = match runnable.runnable_type {
+            RunnableType::Binary => {
+                let binary_name = runnable.target_name.as_str();
+                let runs_by_default = runnable.package_binary_count == 1
+                    || runnable.default_run.as_deref() == Some(binary_name);
+                let args = if runs_by_default {
+                    vec!["run".to_string(), format!("--package={}", runnable.package)]
+                } else {
+                    vec![
+                        "run".to_string(),
+                        format!("--bin={}", binary_name),
+                        format!("--package={}", runnable.package),
+                    ]
+                };
+                (format!("Debug binary '{}'", runnable.name), args, None, vec![])
+            },
+            RunnableType::Example => {
+                let args = vec![
+                    "run".to_string(),
+                    format!("--example={}", runnable.target_name),
+                    format!("--package={}", runnable.package),
+                ];
+                (format!("Debug example '{}'", runnable.name), args, None, vec![])
+            },
+            RunnableType::Test(ref test_target) => {
+                let target_name = runnable.target_name.as_str();
+                let (target_arg, filter_kind) = match test_target {
+                    TestTarget::Lib => ("--lib".to_string(), "lib"),
+                    TestTarget::Bin => (format!("--bin={}", target_name), "bin"),
+                    TestTarget::Integration => (format!("--test={}", target_name), "test"),
+                };
+                // Build the test harness without running it; CodeLLDB launches the produced binary
+                let args = vec![
+                    "test".to_string(),
+                    "--no-run".to_string(),
+                    format!("--package={}", runnable.package),
+                    target_arg,
+                ];
+                let filter = CargoFilter {
+                    name: target_name.to_string(),
+                    kind: filter_kind.to_string(),
+                };
+                let name = match test_target {
+                    TestTarget::Integration => format!("Debug integration test '{}::{}'", runnable.package, target_name),
+                    TestTarget::Lib | TestTarget::Bin => {
+                        format!("Debug unit tests '{}::{} ({})'", runnable.package, target_name, filter_kind)
+                    },
+                };
+                (name, args, Some(filter), vec![])
+            },
+            RunnableType::Bench { harness } => {
+                let bench_name = runnable.target_name.as_str();
+                // Build the bench binary without running it; CodeLLDB launches the produced binary
+                let args = vec![
+                    "bench".to_string(),
+                    "--no-run".to_string(),
+                    format!("--bench={}", bench_name),
+                    format!("--package={}", runnable.package),
+                ];
+                let filter = CargoFilter {
+                    name: bench_name.to_string(),
+                    kind: "bench".to_string(),
+                };
+                // Benches without the libtest harness are plain binaries; pass `--bench` as
+                // `cargo bench` does so e.g. Criterion runs in benchmark rather than test mode
+                let program_args = if harness { vec![] } else { vec!["--bench".to_string()] };
+                (format!("Debug bench '{}'", runnable.name), args, Some(filter), program_args)
+            },
+        };
+        let name = match options.name_features {
+            true if !runnable.required_features.is_empty() => format!("{} [{}]", name, feature_label(&runnable.required_features)),
+            _ => name,
+        };
+
+        // Explicit --env values win over the package's metadata, which wins over the defaults
+        let mut env = options.default_env.clone();
+        env.extend(runnable.overrides.env.clone());
+        env.extend(options.env.clone());
+
+        // Bevy resolves assets relative to BEVY_ASSET_ROOT; an explicit value still wins
+        if runnable.depends_on_bevy {
+            env.entry("BEVY_ASSET_ROOT".to_string()).or_insert_with(|| package_dir.clone());
+        }
+
+        // cppdbg/cppvsdbg take the environment as a list of name/value pairs rather than a map
+        let environment = (!env.is_empty()).then(|| {
+            env.iter()
+                .map(|(name, value)| EnvironmentEntry { name: name.clone(), value: value.clone() })
+                .collect::<Vec<_>>()
+        });
+
+        let features = runnable_features(runnable, options);
+
+        // Arguments from --args-file, or else the package's metadata, follow any the target kind
+        // itself needs
+        let mut program_args = program_args;
+        if let Some(extra_args) = options.program_args.get(&runnable.name).or(runnable.overrides.args.as_ref()) {
+            program_args.extend(extra_args.iter().cloned());
+        }
+
+        // A template replaces the built-in configuration shape entirely
+        if let Some(template) = &options.template {
+            let kind = match runnable.runnable_type {
+                RunnableType::Binary => "bin",
+                RunnableType::Example => "example",
+                RunnableType::Test(_) => "test",
+                RunnableType::Bench { .. } => "bench",
+            };
+            let values = [
+                ("name", runnable.name.clone()),
+                ("package", runnable.package.clone()),
+                ("bin", runnable.target_name.clone()),
+                ("kind", kind.to_string()),
+                ("cwd", cwd.clone()),
+                ("manifest_path", format!("{}/Cargo.toml", package_dir)),
+                ("features", features.join(",")),
+            ];
+            match template.render(&values) {
+                Ok(configuration) => {
+                    if runnable.runnable_type == RunnableType::Binary {
+                        compound_members.push(configurations.len());
+                    }
+                    configurations.push(configuration);
+                    origins.push(&runnable.project_path);
+                },
+                Err(message) => warn!("Skipping '{}': the template does not render: {}", runnable.name, message),
+            }
+            continue;
+        }
+
+        // Emit one configuration per requested profile
+        for &profile in &options.profiles {
+            let profile_suffix = profile.name_suffix(options.profiles.len() > 1);
+            let name = match profile_suffix {
+                Some(suffix) => format!("{} ({})", name, suffix),
+                None => name.clone(),
+            };
+
+            let mut cargo_args = cargo_args.clone();
+            if profile == Profile::Release {
+                cargo_args.push("--release".to_string());
+            }
+            if let Some(target) = build_target(runnable, options) {
+                cargo_args.push(format!("--target={}", target));
+            }
+
+            if options.no_default_features {
+                cargo_args.push("--no-default-features".to_string());
+            }
+            if !features.is_empty() {
+                cargo_args.push(format!("--features={}", features.join(",")));
+            }
+
+            // Add manifest path to ensure proper workspace context
+            cargo_args.push(manifest_path_arg.clone());
+            let env = env.clone();
+            let environment = environment.clone();
+            let program_args = program_args.clone();
+            let filter = filter.clone();
+
+            // The label must match the task emitted by generate_workspace_tasks
+            let pre_launch_task = options.pre_launch_build.then(|| build_task_label(&runnable.package, profile));
+            let stop_on_entry = options.stop_at_entry.then_some(true);
+
+            let config = match options.debugger {
+                Debugger::Lldb => {
+                    // Tests and benches have no fixed artifact path, so they keep the cargo block
+                    let program = options.program_mode
+                        .then(|| artifact_program_path(runnable, root_dir, profile, options))
+                        .flatten();
+                    // The package build skips examples and feature-gated binaries, so a launched
+                    // artifact is built on its own
+                    let pre_launch_task = match program {
+                        Some(_) => options.pre_launch_build.then(|| artifact_build_task_label(runnable, profile)),
+                        None => pre_launch_task,
+                    };
+                    Configuration {
+                        name,
+                        config_type: "lldb".to_string(),
+                        request: "launch".to_string(),
+                        cwd: cwd.clone(),
+                        env,
+                        cargo: program.is_none().then_some(CargoConfig {
+                            args: cargo_args,
+                            filter,
+                        }),
+                        program,
+                        mi_mode: None,
+                        mi_debugger_path: None,
+                        environment: None,
+                        args: program_args,
+                        pre_launch_task,
+                        stop_on_entry,
+                        stop_at_entry: None,
+                        terminal: options.terminal,
+                        env_file: env_file.clone(),
+                        init_commands: options.init_commands.clone(),
+                        source_map: options.source_map.clone(),
+                        pid: None,
+                        process_id: None,
+                        other: serde_json::Map::new(),
+                    }
+                },
+                Debugger::Gdb => {
+                    // cppdbg can't build through cargo, so point it at the artifact cargo produces
+                    let Some(program) = artifact_program_path(runnable, root_dir, profile, options) else {
+                        skipped_without_program += 1;
+                        continue;
+                    };
+                    let pre_launch_task = options.pre_launch_build.then(|| artifact_build_task_label(runnable, profile));
+                    Configuration {
+                        name,
+                        config_type: "cppdbg".to_string(),
+                        request: "launch".to_string(),
+                        cwd: cwd.clone(),
+                        env: BTreeMap::new(),
+                        cargo: None,
+                        program: Some(program),
+                        mi_mode: Some("gdb".to_string()),
+                        mi_debugger_path: Some(gdb_path(options)),
+                        environment,
+                        args: program_args,
+                        pre_launch_task,
+                        stop_on_entry: None,
+                        stop_at_entry: stop_on_entry,
+                        terminal: None,
+                        env_file: env_file.clone(),
+                        init_commands: Vec::new(),
+                        source_map: BTreeMap::new(),
+                        pid: None,
+                        process_id: None,
+                        other: serde_json::Map::new(),
+                    }
+                },
+                Debugger::Cppvsdbg => {
+                    // Like cppdbg, the Visual Studio debugger launches the executable cargo produced
+                    let Some(program) = artifact_program_path(runnable, root_dir, profile, options) else {
+                        skipped_without_program += 1;
+                        continue;
+                    };
+                    let pre_launch_task = options.pre_launch_build.then(|| artifact_build_task_label(runnable, profile));
+                    Configuration {
+                        name,
+                        config_type: "cppvsdbg".to_string(),
+                        request: "launch".to_string(),
+                        cwd: cwd.clone(),
+                        env: BTreeMap::new(),
+                        cargo: None,
+                        program: Some(program),
+                        mi_mode: None,
+                        mi_debugger_path: None,
+                        environment,
+                        args: program_args,
+                        pre_launch_task,
+                        stop_on_entry: None,
+                        stop_at_entry: stop_on_entry,
+                        terminal: None,
+                        env_file: env_file.clone(),
+                        init_commands: Vec::new(),
+                        source_map: BTreeMap::new(),
+                        pid: None,
+                        process_id: None,
+                        other: serde_json::Map::new(),
+                    }
+                },
+                Debugger::LldbDap => {
+                    // lldb-dap has no cargo integration, so it launches the artifact of a separate build
+                    let Some(program) = artifact_program_path(runnable, root_dir, profile, options) else {
+                        skipped_without_program += 1;
+                        continue;
+                    };
+                    let pre_launch_task = options.pre_launch_build.then(|| artifact_build_task_label(runnable, profile));
+                    Configuration {
+                        name,
+                        config_type: "lldb-dap".to_string(),
+                        request: "launch".to_string(),
+                        cwd: cwd.clone(),
+                        env,
+                        cargo: None,
+                        program: Some(program),
+                        mi_mode: None,
+                        mi_debugger_path: None,
+                        environment: None,
+                        args: program_args,
+                        pre_launch_task,
+                        stop_on_entry,
+                        stop_at_entry: None,
+                        terminal: None,
+                        // lldb-dap has no equivalent of envFile
+                        env_file: None,
+                        init_commands: options.init_commands.clone(),
+                        source_map: options.source_map.clone(),
+                        pid: None,
+                        process_id: None,
+                        other: serde_json::Map::new(),
+                    }
+                },
+            };
+            let config = match &options.config_type {
+                Some(config_type) => Configuration { config_type: config_type.clone(), ..config },
+                None => config,
+            };
+
+            // The compound starts each binary once, in the first requested profile
+            if matches!(runnable.runnable_type, RunnableType::Binary) && profile == options.profiles[0] {
+                compound_members.push(configurations.len());
+            }
+
+            configurations.push(config);
+            origins.push(&runnable.project_path);
+
+            if options.with_attach
+                && matches!(runnable.runnable_type, RunnableType::Binary)
+                && let Some(attach) = attach_configuration(runnable, root_dir, profile, profile_suffix, &cwd, options)
+            {
+                configurations.push(attach);
+                origins.push(&runnable.project_path);
+            }
+
+            if let Some(remote) = &options.remote
+                && matches!(runnable.runnable_type, RunnableType::Binary)
+                && let Some(config) = remote_configuration(runnable, root_dir, profile, profile_suffix, &cwd, remote, options)
+            {
+                configurations.push(config);
+                origins.push(&runnable.project_path);
+            }
+        }
+    }
+
+    // One line for the whole run; every unit-test target would otherwise warn on its own
+    if skipped_without_program > 0 {
+        let debugger = match options.debugger {
+            Debugger::Lldb => "CodeLLDB",
+            Debugger::Gdb => "GDB",
+            Debugger::Cppvsdbg => "cppvsdbg",
+            Debugger::LldbDap => "lldb-dap",
+        };
+        warn!(
+            "Skipped {} test and bench configurations: {} configurations need a fixed binary path, which test and bench harnesses don't have",
+            skipped_without_program, debugger,
+        );
+    }
+
+    if !options.raw_names {
+        for config in &mut configurations {
+            config.name = sanitize_name(&config.name);
+        }
+    }
+    make_names_unique(&mut configurations, &origins, root_dir);
+    
+    let compounds = options.compound_all.then(|| {
+        vec![Compound {
+            name: RUN_ALL_BINARIES_COMPOUND.to_string(),
+            configurations: compound_members.iter().map(|&index| configurations[index].name.clone()).collect(),
+        }]
+    });
+
+    LaunchConfig {
+        version: "0.2.0".to_string(),
+        configurations,
+        compounds,
+    }
+}
+
+/// Lists features for a configuration name, cutting long lists short so the launch dropdown stays
+/// readable.
+fn feature_label(features: &[String]) -> String {
+    const SHOWN_FEATURES: usize = 3;
+    match features.len().checked_sub(SHOWN_FEATURES) {
+        Some(hidden) if hidden > 0 => format!("{}, +{} more", features[..SHOWN_FEATURES].join(", "), hidden),
+        _ => features.join(", "),
+    }
+}
+
+/// Expresses a project directory relative to the workspace root as a `${workspaceFolder}` path.
+fn workspace_relative_path(project_path: &Path, root_dir: &Path) -> String {
+    // Calculate relative path from root to project
+    let relative_path = match pathdiff::diff_paths(project_path, root_dir) {
+        Some(path) => path,
+        None => project_path.to_path_buf(),
+    };
+    
+    if relative_path == Path::new("") || relative_path == Path::new(".") {
+        "${workspaceFolder}".to_string()
+    } else {
+        format!("${{workspaceFolder}}/{}", relative_path.display())
+    }
+}
+
+/// Normalizes a configuration name for the launch dropdown: control characters are dropped and
+/// runs of whitespace (including line breaks) become a single space, with none at either end.
+fn sanitize_name(name: &str) -> String {
+    name.split_whitespace()
+        .map(|word| word.chars().filter(|c| !c.is_control()).collect::<String>())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// VS Code picks configurations by name, so each must be unique. Separate projects can contain
+/// packages with the same name; their configurations are qualified with the project folder, and
+/// anything still colliding is numbered.
+fn make_names_unique(configurations: &mut [Configuration], origins: &[&Path], root_dir: &Path) {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for config in configurations.iter() {
+        *counts.entry(config.name.clone()).or_default() += 1;
+    }
+    for (config, origin) in configurations.iter_mut().zip(origins) {
+        if counts[&config.name] > 1 {
+            config.name = format!("{} [{}]", config.name, folder_path(origin, root_dir));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for config in configurations.iter_mut() {
+        let base = config.name.clone();
+        let mut number = 2;
+        while !seen.insert(config.name.clone()) {
+            config.name = format!("{} #{}", base, number);
+            number += 1;
+        }
+    }
+}
+
+/// Expresses a project directory relative to the workspace file, as written in its `folders`.
+pub(crate) fn folder_path(project_path: &Path, root_dir: &Path) -> String {
+    match pathdiff::diff_paths(project_path, root_dir) {
+        Some(path) if path != Path::new("") && path != Path::new(".") => format!("./{}", path.display()),
+        _ => ".".to_string(),
+    }
+}
+
+/// Builds a configuration that attaches to an already running instance of a binary, picked from
+/// the process list when the session starts.
+fn attach_configuration(
+    runnable: &Runnable,
+    root_dir: &Path,
+    profile: Profile,
+    profile_suffix: Option<&str>,
+    cwd: &str,
+    options: &LaunchOptions,
+) -> Option<Configuration> {
+    let name = match profile_suffix {
+        Some(suffix) => format!("Attach to '{}' ({})", runnable.name, suffix),
+        None => format!("Attach to '{}'", runnable.name),
+    };
+    // The debugger loads symbols from the artifact, so nothing is built through cargo
+    let program = artifact_program_path(runnable, root_dir, profile, options)?;
+    let pick_process = Some("${command:pickProcess}".to_string());
+
+    let (config_type, mi_mode, mi_debugger_path, pid, process_id) = match options.debugger {
+        Debugger::Lldb => ("lldb", None, None, pick_process, None),
+        Debugger::Gdb => ("cppdbg", Some("gdb".to_string()), Some(gdb_path(options)), None, pick_process),
+        Debugger::Cppvsdbg => ("cppvsdbg", None, None, None, pick_process),
+        Debugger::LldbDap => ("lldb-dap", None, None, pick_process, None),
+    };
+    let is_lldb = matches!(options.debugger, Debugger::Lldb | Debugger::LldbDap);
+
+    Some(Configuration {
+        name,
+        config_type: options.config_type.clone().unwrap_or_else(|| config_type.to_string()),
+        request: "attach".to_string(),
+        cwd: cwd.to_string(),
+        env: BTreeMap::new(),
+        cargo: None,
+        program: Some(program),
+        mi_mode,
+        mi_debugger_path,
+        environment: None,
+        args: Vec::new(),
+        pre_launch_task: None,
+        stop_on_entry: None,
+        stop_at_entry: None,
+        terminal: None,
+        env_file: None,
+        init_commands: if is_lldb { options.init_commands.clone() } else { Vec::new() },
+        source_map: if is_lldb { options.source_map.clone() } else { BTreeMap::new() },
+        pid,
+        process_id,
+        other: serde_json::Map::new(),
+    })
+}
+
+/// Builds a CodeLLDB configuration that connects to a debug server on a remote device, loading
+/// symbols from the locally built binary.
+fn remote_configuration(
+    runnable: &Runnable,
+    root_dir: &Path,
+    profile: Profile,
+    profile_suffix: Option<&str>,
+    cwd: &str,
+    remote: &RemoteTarget,
+    options: &LaunchOptions,
+) -> Option<Configuration> {
+    let name = match profile_suffix {
+        Some(suffix) => format!("Remote debug '{}' ({})", runnable.name, suffix),
+        None => format!("Remote debug '{}'", runnable.name),
+    };
+    let program = artifact_program_path(runnable, root_dir, profile, options)?;
+
+    // The remote fields are CodeLLDB's, whichever debugger the other configurations use
+    let mut init_commands = options.init_commands.clone();
+    init_commands.push(format!("gdb-remote {}:{}", remote.host, remote.port));
+
+    Some(Configuration {
+        name,
+        config_type: options.config_type.clone().unwrap_or_else(|| "lldb".to_string()),
+        request: "attach".to_string(),
+        cwd: cwd.to_string(),
+        env: BTreeMap::new(),
+        cargo: None,
+        program: Some(program),
+        mi_mode: None,
+        mi_debugger_path: None,
+        environment: None,
+        args: Vec::new(),
+        pre_launch_task: options.pre_launch_build.then(|| artifact_build_task_label(runnable, profile)),
+        stop_on_entry: None,
+        stop_at_entry: None,
+        terminal: None,
+        env_file: None,
+        init_commands,
+        source_map: options.source_map.clone(),
+        pid: None,
+        process_id: None,
+        other: serde_json::Map::new(),
+    })
+}
+
+/// The features a runnable is built with: its required features first, then any extra ones
+/// from --features.
+fn runnable_features(runnable: &Runnable, options: &LaunchOptions) -> Vec<String> {
+    let mut features = runnable.required_features.clone();
+    for feature in &options.features {
+        if !features.contains(feature) {
+            features.push(feature.clone());
+        }
+    }
+    features
+}
+
+/// The triple a runnable is built for: `--target` if given, else `CARGO_BUILD_TARGET` or `[build]
+/// target` from the project's cargo config. Passing it explicitly matters because the debugger
+/// runs cargo from the workspace root, where that config file isn't picked up.
+fn build_target<'a>(runnable: &'a Runnable, options: &'a LaunchOptions) -> Option<&'a str> {
+    options.target.as_deref().or(runnable.build_target.as_deref())
+}
+
+/// Returns the path of the executable cargo builds for a runnable, expressed relative to
+/// `${workspaceFolder}` when the target directory lives under the root.
+///
+/// Test and bench harnesses are written to `deps/` with a hash suffix, so they have no fixed path.
+fn artifact_program_path(runnable: &Runnable, root_dir: &Path, profile: Profile, options: &LaunchOptions) -> Option<String> {
+    let target_name = &runnable.target_name;
+    let target = build_target(runnable, options);
+    let exe_suffix = exe_suffix(target);
+    // Cross builds land in a per-triple directory: target/<triple>/<profile>
+    let profile_dir = match target {
+        Some(triple) => PathBuf::from(triple).join(profile.target_subdirectory()),
+        None => PathBuf::from(profile.target_subdirectory()),
+    };
+    let relative_artifact = match runnable.runnable_type {
+        RunnableType::Binary => profile_dir.join(format!("{}{}", target_name, exe_suffix)),
+        RunnableType::Example => profile_dir
+            .join("examples")
+            .join(format!("{}{}", target_name, exe_suffix)),
+        RunnableType::Test(_) | RunnableType::Bench { .. } => return None,
+    };
+
+    let artifact = runnable.target_directory.join(relative_artifact);
+    Some(match pathdiff::diff_paths(&artifact, root_dir) {
+        Some(path) if !path.starts_with("..") => format!("${{workspaceFolder}}/{}", path.display()),
+        _ => artifact.display().to_string(),
+    })
+}
+
+/// Picks cppvsdbg when the active Rust toolchain targets MSVC, since CodeLLDB can't read PDB
+/// debug info well, and CodeLLDB everywhere else.
+pub fn detect_default_debugger() -> Debugger {
+    let host = host_triple();
+    let debugger = match host {
+        Some(host) if host.ends_with("-pc-windows-msvc") => Debugger::Cppvsdbg,
+        _ => Debugger::Lldb,
+    };
+
+    let name = debugger.to_possible_value().map_or_else(String::new, |value| value.get_name().to_string());
+    match host {
+        Some(host) => info!("Host is {}, so using the {} debugger (pass --debugger to change it)", host, name),
+        None => info!("Couldn't ask rustc for the host, so using the {} debugger (pass --debugger to change it)", name),
+    }
+    debugger
+}
+
+/// The suffix cargo gives executables built for `target`, or for the host when no target is set.
+fn exe_suffix(target: Option<&str>) -> &'static str {
+    let is_windows = match target {
+        Some(triple) => triple.contains("windows"),
+        // Without rustc, assume the workspace is debugged where this tool runs
+        None => host_triple().map_or(cfg!(windows), |host| host.contains("windows")),
+    };
+    if is_windows { ".exe" } else { "" }
+}
+
+/// The host triple of the active toolchain, asked of rustc once per run.
+fn host_triple() -> Option<&'static str> {
+    static HOST: OnceLock<Option<String>> = OnceLock::new();
+    HOST.get_or_init(detect_host_triple).as_deref()
+}
+
+fn detect_host_triple() -> Option<String> {
+    // Ask the active toolchain rather than relying on how this tool itself was compiled
+    let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let output = std::process::Command::new(rustc).arg("-vV").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(|host| host.trim().to_string())
+}
+
+/// The `miDebuggerPath` of GDB configurations. Without an explicit path cppdbg resolves `gdb`
+/// from PATH on whichever machine opens the workspace, so the file stays machine-independent.
+fn gdb_path(options: &LaunchOptions) -> String {
+    options.gdb_path.clone().unwrap_or_else(|| "gdb".to_string())
+}
+
+/// Generates the `launch` section of the workspace file. Configuration names are already unique.
+pub fn generate_workspace_launch_config(runnables: &[Runnable], root_dir: &Path, options: &LaunchOptions) -> WorkspaceLaunchConfig {
+    let LaunchConfig { configurations, compounds, .. } = generate_launch_config(runnables, root_dir, options);
+
+    WorkspaceLaunchConfig {
+        version: "0.2.0".to_string(),
+        configurations,
+        compounds,
+    }
+}
+
+/// Label of the generated default build task.
+const BUILD_ALL_TASK: &str = "cargo build (all packages)";
+
+fn build_task_label(package: &str, profile: Profile) -> String {
+    match profile {
+        Profile::Dev => format!("cargo build ({})", package),
+        Profile::Release => format!("cargo build --release ({})", package),
+    }
+}
+
+/// Label of the task building just the binary or example a configuration launches as its
+/// `program`.
+fn artifact_build_task_label(runnable: &Runnable, profile: Profile) -> String {
+    let target_arg = artifact_target_arg(runnable);
+    match profile {
+        Profile::Dev => format!("cargo build {} ({})", target_arg, runnable.package),
+        Profile::Release => format!("cargo build --release {} ({})", target_arg, runnable.package),
+    }
+}
+
+/// The cargo argument selecting a binary or example target.
+fn artifact_target_arg(runnable: &Runnable) -> String {
+    match runnable.runnable_type {
+        RunnableType::Example => format!("--example={}", runnable.target_name),
+        _ => format!("--bin={}", runnable.target_name),
+    }
+}
+
+/// Whether some configuration launches a built artifact behind an [`artifact_build_task_label`]
+/// task.
+fn launches_built_artifacts(options: &LaunchOptions) -> bool {
+    options.pre_launch_build && options.template.is_none()
+        && (options.debugger != Debugger::Lldb || options.program_mode || options.remote.is_some())
+}
+
+/// Generates a VS Code tasks (v2.0.0) object with build, test and clippy tasks for every discovered package.
+pub fn generate_workspace_tasks(runnables: &[Runnable], root_dir: &Path, options: &LaunchOptions) -> serde_json::Value {
+    // Collect unique packages along with their directories
+    let mut packages: Vec<(&str, &Path, bool)> = runnables.iter()
+        .map(|r| (r.package.as_str(), r.package_path.as_path(), r.has_doctests))
+        .collect();
+    packages.sort();
+    packages.dedup();
+
+    let mut tasks = Vec::new();
+    // The build task of every package for the first profile, which the default build task runs
+    let mut default_builds = Vec::new();
+    for (package, package_path, has_doctests) in packages {
+        let project_dir = workspace_relative_path(package_path, root_dir);
+        let manifest_path_arg = format!("--manifest-path={}/Cargo.toml", project_dir);
+
+        default_builds.extend(options.profiles.first().map(|&profile| build_task_label(package, profile)));
+
+        // One build task per profile the launch configurations debug
+        for &profile in &options.profiles {
+            let mut args = vec!["build".to_string(), format!("--package={}", package), manifest_path_arg.clone()];
+            if profile == Profile::Release {
+                args.push("--release".to_string());
+            }
+            if let Some(target) = &options.target {
+                args.push(format!("--target={}", target));
+            }
+            if options.no_default_features {
+                args.push("--no-default-features".to_string());
+            }
+            if !options.features.is_empty() {
+                args.push(format!("--features={}", options.features.join(",")));
+            }
+            tasks.push(serde_json::json!({
+                "label": build_task_label(package, profile),
+                "type": "shell",
+                "command": "cargo",
+                "args": args,
+                "group": "build",
+                "options": { "cwd": project_dir },
+                "problemMatcher": "$rustc",
+            }));
+        }
+
+        // Checks report through the $rustc matcher so their findings land in the Problems panel
+        for (subcommand, group) in [(&["check"][..], "build"), (&["test"], "test"), (&["clippy"], "build"), (&["fmt", "--check"], "test")] {
+            let label = format!("cargo {} ({})", subcommand.join(" "), package);
+            let mut args: Vec<String> = subcommand.iter().map(|s| s.to_string()).collect();
+            args.push(format!("--package={}", package));
+            args.push(manifest_path_arg.clone());
+            tasks.push(serde_json::json!({
+                "label": label,
+                "type": "shell",
+                "command": "cargo",
+                "args": args,
+                "group": group,
+                "options": { "cwd": project_dir },
+                "problemMatcher": "$rustc",
+            }));
+        }
+
+        // Doctests can't be debugged like other targets, but running them is still one task away
+        if has_doctests {
+            tasks.push(serde_json::json!({
+                "label": format!("cargo test --doc ({})", package),
+                "type": "shell",
+                "command": "cargo",
+                "args": ["test", "--doc", format!("--package={}", package), manifest_path_arg],
+                "group": "test",
+                "options": { "cwd": project_dir },
+                "problemMatcher": "$rustc",
+            }));
+        }
+    }
+
+    // Configurations launching a binary or example build exactly that artifact, with its
+    // required features and build target, since the package build task skips examples and
+    // feature-gated binaries
+    if launches_built_artifacts(options) {
+        let mut artifacts: Vec<&Runnable> = runnables.iter()
+            .filter(|r| matches!(r.runnable_type, RunnableType::Binary | RunnableType::Example))
+            .collect();
+        artifacts.sort_by(|a, b| a.package.cmp(&b.package).then_with(|| a.name.cmp(&b.name)));
+        for runnable in artifacts {
+            let project_dir = workspace_relative_path(&runnable.package_path, root_dir);
+            let target_arg = artifact_target_arg(runnable);
+            let features = runnable_features(runnable, options);
+            for &profile in &options.profiles {
+                let mut args = vec!["build".to_string(), format!("--package={}", runnable.package), target_arg.clone()];
+                if profile == Profile::Release {
+                    args.push("--release".to_string());
+                }
+                if let Some(target) = build_target(runnable, options) {
+                    args.push(format!("--target={}", target));
+                }
+                if options.no_default_features {
+                    args.push("--no-default-features".to_string());
+                }
+                if !features.is_empty() {
+                    args.push(format!("--features={}", features.join(",")));
+                }
+                args.push(format!("--manifest-path={}/Cargo.toml", project_dir));
+                tasks.push(serde_json::json!({
+                    "label": artifact_build_task_label(runnable, profile),
+                    "type": "shell",
+                    "command": "cargo",
+                    "args": args,
+                    "group": "build",
+                    "options": { "cwd": project_dir },
+                    "problemMatcher": "$rustc",
+                }));
+            }
+        }
+    }
+
+    // Ctrl+Shift+B builds everything; packages build one after another since they share target
+    // directory locks anyway
+    if !default_builds.is_empty() {
+        tasks.push(serde_json::json!({
+            "label": BUILD_ALL_TASK,
+            "dependsOn": default_builds,
+            "dependsOrder": "sequence",
+            "group": { "kind": "build", "isDefault": true },
+            "problemMatcher": [],
+        }));
+    }
+
+    // Cleaning works on a whole cargo workspace, so there is one task per project
+    let mut projects: Vec<&Path> = runnables.iter().map(|r| r.project_path.as_path()).collect();
+    projects.sort();
+    projects.dedup();
+    for project_path in projects {
+        let project_dir = workspace_relative_path(project_path, root_dir);
+        tasks.push(serde_json::json!({
+            "label": format!("cargo clean ({})", folder_path(project_path, root_dir)),
+            "type": "shell",
+            "command": "cargo",
+            "args": ["clean", format!("--manifest-path={}/Cargo.toml", project_dir)],
+            "group": "none",
+            "options": { "cwd": project_dir },
+            "problemMatcher": [],
+        }));
+    }
+
+    serde_json::json!({
+        "version": "2.0.0",
+        "tasks": tasks,
+    })
+}
+
+/// Name of the compound generated by `--compound-all`.
+const RUN_ALL_BINARIES_COMPOUND: &str = "Run all binaries";
+
+/// Generates the recommended-extensions object: rust-analyzer plus the extension providing the chosen debugger.
+pub fn generate_extensions(debugger: Debugger) -> serde_json::Value {
+    let debugger_extension = match debugger {
+        Debugger::Lldb => "vadimcn.vscode-lldb",
+        Debugger::Gdb | Debugger::Cppvsdbg => "ms-vscode.cpptools",
+        Debugger::LldbDap => "llvm-vs-code-extensions.lldb-dap",
+    };
+
+    serde_json::json!({
+        "recommendations": ["rust-lang.rust-analyzer", debugger_extension],
+    })
+}
+
+/// Generates rust-analyzer friendly workspace settings.
+///
+/// `linked_projects` lists the projects whose manifests rust-analyzer should load explicitly; when
+/// it is empty, rust-analyzer discovers them on its own.
+pub fn generate_settings(linked_projects: &[PathBuf], root_dir: &Path) -> serde_json::Value {
+    let mut settings = serde_json::json!({
+        "rust-analyzer.check.command": "clippy",
+        "files.watcherExclude": {
+            "**/target/**": true,
+        },
+        // Keep build artifacts out of the explorer and search results
+        "files.exclude": {
+            "**/target": true,
+        },
+        "search.exclude": {
+            "**/target": true,
+        },
+    });
+
+    if !linked_projects.is_empty() {
+        let mut manifests: Vec<String> = linked_projects.iter()
+            .map(|project| format!("{}/Cargo.toml", folder_path(project, root_dir)))
+            .collect();
+        manifests.sort();
+        settings[LINKED_PROJECTS_SETTING] = serde_json::json!(manifests);
+    }
+    settings
+}
+
+/// Generated from the discovered projects, so it's replaced on every run rather than left to the
+/// user like the other settings.
+pub(crate) const LINKED_PROJECTS_SETTING: &str = "rust-analyzer.linkedProjects";
@@ -0,0 +1,818 @@
+//! Discovery of the Rust projects below a directory and of the runnable targets in each.
+
+use cargo_metadata::{CargoOpt, MetadataCommand, TargetKind};
+use clap::ValueEnum;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::Gitignore;
+use ignore::Match;
+use log::{debug, info, warn};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::Error;
+
+/// Settings that control which directories are searched for Rust projects.
+pub struct DiscoveryOptions {
+    pub respect_gitignore: bool,
+    pub exclude: GlobSet,
+    /// When empty, every discovered project is kept
+    pub include: GlobSet,
+    /// Depth counted from the root, which is depth 0
+    pub max_depth: Option<usize>,
+    pub follow_symlinks: bool,
+    /// Names of further directories to skip, like `target` and `node_modules`
+    pub ignore_dirs: Vec<String>,
+    /// Search directories whose name starts with a dot
+    pub include_hidden: bool,
+    pub features: DiscoveryFeatures,
+    /// When empty, every package is kept
+    pub packages: Vec<String>,
+    /// Only keep the packages listed in the workspace's `default-members`
+    pub default_members_only: bool,
+    /// Per-user directory where `cargo metadata` results are cached between runs; `None` always
+    /// reads them afresh. Entries the current user doesn't own are ignored.
+    pub metadata_cache: Option<PathBuf>,
+}
+
+/// What discovery found: every Rust project, and the runnables of the selected packages.
+pub struct Discovery {
+    /// Directories of all discovered projects, including those without runnables
+    pub projects: Vec<PathBuf>,
+    pub runnables: Vec<Runnable>,
+    /// Projects whose metadata couldn't be read; they keep their folder but have no runnables
+    pub failed_projects: Vec<PathBuf>,
+}
+
+/// Feature set used when reading `cargo metadata` for discovery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiscoveryFeatures {
+    /// `--all-features`
+    All,
+    /// The package's default features
+    Default,
+    /// `--no-default-features`
+    None,
+}
+
+impl DiscoveryFeatures {
+    /// The option to pass to `MetadataCommand`, if any.
+    fn cargo_opt(self) -> Option<CargoOpt> {
+        match self {
+            DiscoveryFeatures::All => Some(CargoOpt::AllFeatures),
+            DiscoveryFeatures::Default => None,
+            DiscoveryFeatures::None => Some(CargoOpt::NoDefaultFeatures),
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            DiscoveryFeatures::All => "all features",
+            DiscoveryFeatures::Default => "default features",
+            DiscoveryFeatures::None => "no default features",
+        }
+    }
+}
+
+/// Bookkeeping carried through a single directory search.
+struct SearchState {
+    /// Stack of .gitignore matchers from the outermost directory to the current one
+    ignores: Vec<Gitignore>,
+    /// Canonical paths of directories already searched
+    visited: HashSet<PathBuf>,
+}
+
+/// A target that gets a launch configuration: a binary, example, test or bench.
+#[derive(Debug, Clone)]
+pub struct Runnable {
+    pub name: String,
+    pub package: String,
+    /// The cargo target's own name, as passed to `--bin`, `--example`, `--test` or `--bench`;
+    /// `name` is only for display
+    pub target_name: String,
+    pub runnable_type: RunnableType,
+    pub required_features: Vec<String>,
+    pub project_path: PathBuf,
+    /// Directory of the package's own manifest; differs from `project_path` for nested workspace members
+    pub package_path: PathBuf,
+    pub target_directory: PathBuf,
+    /// Number of `bin` targets in the package; `cargo run` only picks a binary on its own when there is one
+    pub package_binary_count: usize,
+    /// The package's `default-run` binary, which `cargo run` picks without `--bin`
+    pub default_run: Option<String>,
+    pub depends_on_bevy: bool,
+    /// The package has a library whose doc examples `cargo test --doc` runs
+    pub has_doctests: bool,
+    /// `[build] target` from the nearest `.cargo/config.toml`, the triple cargo builds for by default
+    pub build_target: Option<String>,
+    /// Launch settings from the package's `[package.metadata.vscode]` table
+    pub overrides: VscodeOverrides,
+}
+
+/// Launch settings a package can declare in its manifest. Keys the tool doesn't know are ignored.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct VscodeOverrides {
+    /// Program arguments
+    pub args: Option<Vec<String>>,
+    /// Environment variables, added to those given with --env
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Working directory, relative to the package directory
+    pub cwd: Option<String>,
+}
+
+/// The `[package.metadata.vscode]` table: package-wide settings plus per-target refinements under
+/// `[package.metadata.vscode.targets.<target-name>]`.
+#[derive(Debug, Default, Deserialize)]
+struct VscodeMetadata {
+    #[serde(flatten)]
+    package: VscodeOverrides,
+    #[serde(default)]
+    targets: BTreeMap<String, VscodeOverrides>,
+}
+
+impl VscodeMetadata {
+    /// Settings for one target: its own values win over the package-wide ones.
+    fn overrides_for(&self, target_name: &str) -> VscodeOverrides {
+        let mut overrides = self.package.clone();
+        if let Some(target) = self.targets.get(target_name) {
+            if target.args.is_some() {
+                overrides.args = target.args.clone();
+            }
+            if target.cwd.is_some() {
+                overrides.cwd = target.cwd.clone();
+            }
+            overrides.env.extend(target.env.clone());
+        }
+        overrides
+    }
+}
+
+/// The kind of target behind a runnable. The order is the order configurations are emitted in.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RunnableType {
+    Binary,
+    Example,
+    Test(TestTarget),
+    /// A `benches/` target; `harness` is false for custom-main benches such as Criterion
+    Bench { harness: bool },
+}
+
+/// Which target a test runnable's harness is compiled from.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TestTarget {
+    /// `#[test]` functions inside the library target (`cargo test --lib`)
+    Lib,
+    /// `#[test]` functions inside a binary target (`cargo test --bin=<name>`)
+    Bin,
+    /// An integration test under `tests/` (`cargo test --test=<name>`)
+    Integration,
+}
+
+/// Finds the Rust projects at or below `root_dir`, without reading their metadata: the root itself
+/// when it holds a Cargo.toml, otherwise every project the search options let through.
+pub fn find_projects(root_dir: &Path, options: &DiscoveryOptions) -> Result<Vec<PathBuf>, Error> {
+    // First try to see if the root directory itself is a Rust project
+    if root_dir.join("Cargo.toml").exists() {
+        return Ok(vec![root_dir.to_path_buf()]);
+    }
+
+    // Search for Rust projects in subdirectories
+    let mut found_projects = Vec::new();
+    let mut state = SearchState {
+        ignores: if options.respect_gitignore { ancestor_gitignores(root_dir) } else { Vec::new() },
+        visited: HashSet::new(),
+    };
+    if options.follow_symlinks {
+        state.visited.insert(root_dir.canonicalize().unwrap_or_else(|_| root_dir.to_path_buf()));
+    }
+    find_rust_projects(root_dir, &mut found_projects, options, &mut state)?;
+
+    // Excluded directories were already pruned during the search, so excludes take precedence
+    if !options.include.is_empty() {
+        found_projects.retain(|project| {
+            project.strip_prefix(root_dir).is_ok_and(|relative| options.include.is_match(relative))
+        });
+    }
+    Ok(found_projects)
+}
+
+/// Finds the Rust projects at or below `root_dir` and reads the runnables of their packages.
+///
+/// Fails when no project is found at all.
+pub fn discover_runnables(root_dir: &Path, options: &DiscoveryOptions) -> Result<Discovery, Error> {
+    let found_projects = find_projects(root_dir, options)?;
+    if found_projects.is_empty() {
+        return Err(Error::NoProjectsFound { root: root_dir.to_path_buf() });
+    }
+
+    info!("Found {} Rust project(s):", found_projects.len());
+    for project_path in &found_projects {
+        info!("  {}", project_path.display());
+    }
+
+    // Read each project's metadata in parallel; every call shells out to cargo
+    let results: Vec<(&PathBuf, Result<Vec<Runnable>, Error>)> = found_projects
+        .par_iter()
+        .map(|project_path| (project_path, discover_project_runnables(project_path, options)))
+        .collect();
+
+    // One broken project shouldn't hide the others
+    let mut runnables = Vec::new();
+    let mut failed_projects = Vec::new();
+    for (project_path, result) in results {
+        match result {
+            Ok(project_runnables) => runnables.extend(project_runnables),
+            Err(e) => {
+                warn!("{}", e);
+                failed_projects.push(project_path.clone());
+            },
+        }
+    }
+
+    // Keep the output stable regardless of which metadata call finished first
+    runnables.sort_by(|a, b| a.project_path.cmp(&b.project_path).then_with(|| a.name.cmp(&b.name)));
+
+    Ok(Discovery { projects: found_projects, runnables, failed_projects })
+}
+
+/// Runs [`discover_runnables`] for each of `roots` and combines the results. A project reachable
+/// from several roots is only reported once.
+///
+/// Fails only when none of the roots contains a project.
+pub fn discover_runnables_in_roots(roots: &[PathBuf], options: &DiscoveryOptions) -> Result<Discovery, Error> {
+    let mut combined = Discovery { projects: Vec::new(), runnables: Vec::new(), failed_projects: Vec::new() };
+    let mut seen = HashSet::new();
+    let mut first_error = None;
+
+    for root in roots {
+        let discovery = match discover_runnables(root, options) {
+            Ok(discovery) => discovery,
+            // One empty root shouldn't hide the projects of the others
+            Err(e @ Error::NoProjectsFound { .. }) => {
+                if roots.len() > 1 {
+                    warn!("{}", e);
+                }
+                first_error.get_or_insert(e);
+                continue;
+            },
+            Err(e) => return Err(e),
+        };
+        for project in discovery.projects {
+            let canonical = project.canonicalize().unwrap_or_else(|_| project.clone());
+            if seen.insert(canonical) {
+                combined.runnables.extend(discovery.runnables.iter().filter(|r| r.project_path == project).cloned());
+                if discovery.failed_projects.contains(&project) {
+                    combined.failed_projects.push(project.clone());
+                }
+                combined.projects.push(project);
+            }
+        }
+    }
+
+    if let Some(e) = first_error
+        && combined.projects.is_empty() {
+        return Err(e);
+    }
+
+    // A misspelled name shouldn't abort the run, but it deserves a mention. Checked across all
+    // roots, since a package only needs to be found under one of them.
+    for package in &options.packages {
+        if !combined.runnables.iter().any(|r| &r.package == package) {
+            warn!("--package {} did not match any discovered package", package);
+        }
+    }
+
+    Ok(combined)
+}
+
+/// `cargo metadata` output together with the modification times it was read at.
+#[derive(Serialize, Deserialize)]
+struct CachedMetadata {
+    stamps: Vec<(PathBuf, Option<std::time::SystemTime>)>,
+    metadata: cargo_metadata::Metadata,
+}
+
+/// The cache file of a manifest read with the given features.
+fn metadata_cache_file(cache_dir: &Path, manifest_path: &Path, features: DiscoveryFeatures) -> PathBuf {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::hash::DefaultHasher::new();
+    manifest_path.canonicalize().unwrap_or_else(|_| manifest_path.to_path_buf()).hash(&mut hasher);
+    features.description().hash(&mut hasher);
+    cache_dir.join(format!("{:016x}.json", hasher.finish()))
+}
+
+fn modified(path: &Path) -> Option<std::time::SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Whether `path` itself (not a symlink's target) belongs to the user running the tool. Cached
+/// metadata decides which `program` the debugger launches, so nothing another user could have
+/// planted is trusted.
+#[cfg(unix)]
+fn owned_by_current_user(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    // std has no getuid; the home directory belongs to the user running the tool
+    let Some(home) = std::env::home_dir() else {
+        return false;
+    };
+    match (fs::symlink_metadata(path), fs::metadata(home)) {
+        (Ok(entry), Ok(home)) => entry.uid() == home.uid(),
+        _ => false,
+    }
+}
+
+/// On Windows the cache lives in the user's own `%LOCALAPPDATA%`, which other users can't write.
+#[cfg(not(unix))]
+fn owned_by_current_user(_path: &Path) -> bool {
+    true
+}
+
+/// Returns the cached metadata if none of the files it depends on changed since it was read.
+fn read_cached_metadata(cache_file: &Path) -> Option<cargo_metadata::Metadata> {
+    let cache_dir = cache_file.parent()?;
+    if !owned_by_current_user(cache_dir) || !owned_by_current_user(cache_file) {
+        if cache_file.exists() {
+            debug!("Ignoring {}: not owned by the current user", cache_file.display());
+        }
+        return None;
+    }
+    let cached: CachedMetadata = serde_json::from_slice(&fs::read(cache_file).ok()?).ok()?;
+    cached.stamps.iter()
+        .all(|(path, stamp)| modified(path) == *stamp)
+        .then_some(cached.metadata)
+}
+
+/// Caches `metadata` together with the modification times of every file it depends on: the
+/// manifests and lock file, and the directories cargo discovers targets in, whose times change
+/// when a target file is added or removed. Failures only cost the next run a fresh read.
+fn write_cached_metadata(cache_file: &Path, manifest_path: &Path, metadata: &cargo_metadata::Metadata) {
+    let workspace_root = metadata.workspace_root.as_std_path();
+    let mut paths = vec![
+        manifest_path.to_path_buf(),
+        workspace_root.join("Cargo.toml"),
+        workspace_root.join("Cargo.lock"),
+    ];
+    for id in &metadata.workspace_members {
+        let package_manifest = metadata[id].manifest_path.as_std_path();
+        paths.push(package_manifest.to_path_buf());
+        if let Some(package_dir) = package_manifest.parent() {
+            for dir in ["src", "src/bin", "examples", "tests", "benches"] {
+                paths.push(package_dir.join(dir));
+            }
+        }
+    }
+    let stamps = paths.into_iter().map(|path| {
+        let stamp = modified(&path);
+        (path, stamp)
+    }).collect();
+
+    let cached = CachedMetadata { stamps, metadata: metadata.clone() };
+    let cache_dir = cache_file.parent().unwrap_or(Path::new("."));
+    let written = create_private_dir(cache_dir).and_then(|_| {
+        if !owned_by_current_user(cache_dir) {
+            return Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "directory owned by another user"));
+        }
+        // Renamed into place so the file is always the current user's, even replacing a foreign one
+        let temp_file = cache_file.with_extension(format!("tmp-{}", std::process::id()));
+        fs::write(&temp_file, serde_json::to_vec(&cached)?)?;
+        fs::rename(&temp_file, cache_file)
+    });
+    if let Err(e) = written {
+        debug!("Could not cache metadata in {}: {}", cache_file.display(), e);
+    }
+}
+
+/// Creates `dir` (and its parents) if needed, readable only by the current user.
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)
+}
+
+fn discover_project_runnables(project_path: &Path, options: &DiscoveryOptions) -> Result<Vec<Runnable>, Error> {
+    let mut runnables = Vec::new();
+    let manifest_path = project_path.join("Cargo.toml");
+    
+    // Get metadata for the workspace or single package
+    let mut command = MetadataCommand::new();
+    command.manifest_path(&manifest_path);
+    if let Some(features) = options.features.cargo_opt() {
+        command.features(features);
+    }
+    let cache_file = options.metadata_cache.as_deref().map(|dir| metadata_cache_file(dir, &manifest_path, options.features));
+    let metadata = match cache_file.as_deref().and_then(read_cached_metadata) {
+        Some(metadata) => {
+            debug!("Reused cached metadata for {}", manifest_path.display());
+            metadata
+        },
+        None => {
+            let started = std::time::Instant::now();
+            let metadata = command.exec()
+                .map_err(|source| Error::MetadataFailed { path: manifest_path.clone(), source })?;
+            debug!("Read metadata for {} in {:.2?}", manifest_path.display(), started.elapsed());
+            if let Some(cache_file) = &cache_file {
+                write_cached_metadata(cache_file, &manifest_path, &metadata);
+            }
+            metadata
+        },
+    };
+
+    // Canonicalize the project path for consistent comparison
+    let canonical_project_path = project_path.canonicalize().unwrap_or_else(|_| project_path.to_path_buf());
+
+    // `workspace_members` lists exactly the packages of the workspace this manifest belongs to;
+    // a standalone crate is its own single-member workspace. Path dependencies that merely live
+    // under the project directory aren't members and must not produce runnables.
+    let canonical_workspace_root = metadata.workspace_root.as_std_path().canonicalize()
+        .unwrap_or_else(|_| metadata.workspace_root.as_std_path().to_path_buf());
+    let owns_workspace = canonical_workspace_root == canonical_project_path;
+    // A virtual manifest has no package of its own, so its members are all there is; they may
+    // live anywhere, so they're taken from the member list rather than by their location
+    if owns_workspace && metadata.root_package().is_none() {
+        debug!("{} is a virtual workspace with {} member(s)", manifest_path.display(), metadata.workspace_members.len());
+    }
+    let packages_to_process: Vec<&cargo_metadata::Package> = metadata.workspace_members.iter()
+        .map(|id| &metadata[id])
+        .filter(|p| {
+            // The workspace root owns all of its members; a project that is itself a member of an
+            // enclosing workspace only owns the members under its own directory
+            if owns_workspace {
+                return true;
+            }
+            let pkg_manifest_dir = p.manifest_path.parent().unwrap_or(&p.manifest_path);
+            let pkg_canonical_dir = pkg_manifest_dir.as_std_path().canonicalize()
+                .unwrap_or_else(|_| pkg_manifest_dir.as_std_path().to_path_buf());
+            pkg_canonical_dir.starts_with(&canonical_project_path)
+        })
+        .collect();
+
+    if packages_to_process.is_empty() {
+        warn!("No packages found for project {}", project_path.display());
+        return Ok(Vec::new());
+    }
+
+    // Narrow down to the packages selected with --package
+    let packages_to_process: Vec<&cargo_metadata::Package> = packages_to_process.into_iter()
+        .filter(|p| options.packages.is_empty() || options.packages.iter().any(|name| *name == *p.name))
+        .collect();
+
+    // Narrow down to the workspace's default members, the packages `cargo run` picks from without
+    // --package; cargo before 1.71 doesn't report them
+    let packages_to_process: Vec<&cargo_metadata::Package> = if options.default_members_only && metadata.workspace_default_members.is_available() {
+        packages_to_process.into_iter()
+            .filter(|p| metadata.workspace_default_members.contains(&p.id))
+            .collect()
+    } else {
+        packages_to_process
+    };
+
+    // Process targets for each package
+    for package in packages_to_process {
+        // `cargo metadata` doesn't report `harness`, so read it from the manifest
+        let harnessless_benches = find_benches_without_harness(package.manifest_path.as_std_path());
+        let depends_on_bevy = package_depends_on_bevy(&metadata, package);
+        let package_path = package_directory(package, project_path, &canonical_project_path);
+        let vscode_metadata = read_vscode_metadata(package);
+        let package_binary_count = package.targets.iter().filter(|t| t.kind.contains(&TargetKind::Bin)).count();
+        let build_target = cargo_config_build_target(&package_path);
+        let has_doctests = package.targets.iter()
+            .any(|t| t.doctest && t.kind.iter().any(|k| matches!(k, TargetKind::Lib | TargetKind::RLib | TargetKind::ProcMacro)));
+        // A proc-macro package's code only runs inside the compiler, so none of its binaries or
+        // examples is worth a launch configuration; it keeps its folder and tests
+        let is_proc_macro = package.targets.iter().any(|t| t.kind.contains(&TargetKind::ProcMacro));
+        if is_proc_macro {
+            debug!("{} is a proc-macro package; skipping its binaries and examples", package.name);
+        }
+
+        // Fields every runnable of this package shares, whatever kind of target it comes from
+        let new_runnable = |target: &cargo_metadata::Target, name: String, runnable_type: RunnableType| Runnable {
+            name,
+            package: package.name.to_string(),
+            target_name: target.name.clone(),
+            runnable_type,
+            required_features: target.required_features.clone(),
+            project_path: project_path.to_path_buf(),
+            package_path: package_path.clone(),
+            target_directory: metadata.target_directory.clone().into_std_path_buf(),
+            package_binary_count,
+            default_run: package.default_run.clone(),
+            depends_on_bevy,
+            has_doctests,
+            build_target: build_target.clone(),
+            overrides: vscode_metadata.overrides_for(&target.name),
+        };
+
+        // Process targets for this package
+        for target in &package.targets {
+            if target.kind.contains(&TargetKind::Bin) && !is_proc_macro {
+                runnables.push(new_runnable(target, format!("{}::{}", package.name, target.name), RunnableType::Binary));
+            }
+
+            // Add example targets
+            if target.kind.contains(&TargetKind::Example) && !is_proc_macro {
+                runnables.push(new_runnable(target, format!("{}::{} (example)", package.name, target.name), RunnableType::Example));
+            }
+
+            // Add test targets: integration tests plus the unit tests compiled into lib/bin targets
+            let test_target = if target.kind.contains(&TargetKind::Test) {
+                Some((TestTarget::Integration, "test"))
+            } else if !target.test {
+                None
+            } else if target.kind.contains(&TargetKind::Bin) {
+                Some((TestTarget::Bin, "bin test"))
+            } else if target.kind.iter().any(|k| matches!(k,
+                TargetKind::Lib | TargetKind::RLib | TargetKind::DyLib | TargetKind::CDyLib
+                | TargetKind::StaticLib | TargetKind::ProcMacro)) {
+                Some((TestTarget::Lib, "lib test"))
+            } else {
+                None
+            };
+
+            if let Some((test_target, suffix)) = test_target {
+                runnables.push(new_runnable(target, format!("{}::{} ({})", package.name, target.name, suffix), RunnableType::Test(test_target)));
+            }
+
+            // Add bench targets
+            if target.kind.contains(&TargetKind::Bench) {
+                let harness = !harnessless_benches.contains(&target.name);
+                runnables.push(new_runnable(target, format!("{}::{} (bench)", package.name, target.name), RunnableType::Bench { harness }));
+            }
+        }
+    }
+
+    Ok(runnables)
+}
+
+/// Returns the package's manifest directory expressed under `project_path`, so it uses the same
+/// (possibly relative) form as the discovered project paths.
+fn package_directory(package: &cargo_metadata::Package, project_path: &Path, canonical_project_path: &Path) -> PathBuf {
+    let manifest_dir = package.manifest_path.parent().unwrap_or(&package.manifest_path).as_std_path();
+    let canonical_manifest_dir = manifest_dir.canonicalize().unwrap_or_else(|_| manifest_dir.to_path_buf());
+
+    match canonical_manifest_dir.strip_prefix(canonical_project_path) {
+        Ok(relative) if relative.as_os_str().is_empty() => project_path.to_path_buf(),
+        Ok(relative) => project_path.join(relative),
+        // Members outside the project directory keep their absolute location
+        Err(_) => manifest_dir.to_path_buf(),
+    }
+}
+
+fn package_depends_on_bevy(metadata: &cargo_metadata::Metadata, package: &cargo_metadata::Package) -> bool {
+    let is_bevy = |name: &str| name == "bevy" || name == "bevy_asset";
+
+    // Without a resolve graph only the direct dependencies are known
+    let Some(resolve) = &metadata.resolve else {
+        return package.dependencies.iter().any(|d| is_bevy(&d.name));
+    };
+
+    // Walk the resolved dependency graph so crates that only pull in bevy through a
+    // workspace helper crate are detected too
+    let mut visited = HashSet::new();
+    let mut pending = vec![&package.id];
+    while let Some(id) = pending.pop() {
+        if !visited.insert(id) {
+            continue;
+        }
+        let Some(node) = resolve.nodes.iter().find(|n| &n.id == id) else {
+            continue;
+        };
+        for dep in &node.deps {
+            if is_bevy(&metadata[&dep.pkg].name) {
+                return true;
+            }
+            pending.push(&dep.pkg);
+        }
+    }
+
+    false
+}
+
+fn find_benches_without_harness(manifest_path: &Path) -> Vec<String> {
+    let Ok(content) = fs::read_to_string(manifest_path) else {
+        return Vec::new();
+    };
+    let Ok(manifest) = content.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+
+    manifest.get("bench")
+        .and_then(|b| b.as_array())
+        .map(|benches| {
+            benches.iter()
+                .filter(|b| b.get("harness").and_then(|h| h.as_bool()) == Some(false))
+                .filter_map(|b| b.get("name").and_then(|n| n.as_str()).map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Reads the build target cargo would use in `dir`: `CARGO_BUILD_TARGET` if set, else `[build]
+/// target` from the `.cargo/config` or `.cargo/config.toml` files at and above `dir`. As in cargo,
+/// the nearest file that sets it wins, and the legacy `config` is read when a directory has both.
+fn cargo_config_build_target(dir: &Path) -> Option<String> {
+    if let Some(target) = std::env::var("CARGO_BUILD_TARGET").ok().filter(|target| !target.is_empty()) {
+        return Some(target);
+    }
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    dir.ancestors()
+        .flat_map(|ancestor| ["config", "config.toml"].map(|name| ancestor.join(".cargo").join(name)))
+        .filter_map(|path| fs::read_to_string(path).ok()?.parse::<toml::Table>().ok())
+        .find_map(|config| {
+            match config.get("build")?.get("target")? {
+                toml::Value::String(target) => Some(target.clone()),
+                // Several targets may be listed; the first one stands in for all of them
+                toml::Value::Array(targets) => targets.first()?.as_str().map(str::to_string),
+                _ => None,
+            }
+        })
+}
+
+/// Whether `triple` is a WebAssembly target, whose artifacts can't run under a native debugger.
+pub fn is_wasm_target(triple: &str) -> bool {
+    triple.starts_with("wasm32-") || triple.starts_with("wasm64-")
+}
+
+/// Searches `root_dir` depth-first for directories holding a Cargo.toml, in the order the
+/// directories are listed. Uses an explicit stack rather than recursion so that very deep trees
+/// can't overflow the call stack.
+fn find_rust_projects(root_dir: &Path, projects: &mut Vec<PathBuf>, options: &DiscoveryOptions, state: &mut SearchState) -> Result<(), Error> {
+    // Each pending directory remembers its depth and how many .gitignore matchers applied to it
+    let mut pending = vec![(root_dir.to_path_buf(), 0, state.ignores.len())];
+
+    while let Some((dir, depth, ignore_count)) = pending.pop() {
+        // Drop the matchers of directories whose subtrees are done
+        state.ignores.truncate(ignore_count);
+
+        if !dir.is_dir() {
+            continue;
+        }
+
+        // Guard against symlink cycles by never visiting the same real directory twice. This is
+        // checked when the directory is reached rather than when it is listed, so the first path
+        // in search order wins just as it would in a recursive search.
+        if options.follow_symlinks && depth > 0 {
+            let canonical = dir.canonicalize().unwrap_or_else(|_| dir.clone());
+            if !state.visited.insert(canonical) {
+                debug!("Skipping {}: already visited", dir.display());
+                continue;
+            }
+        }
+
+        // Check if this directory contains a Cargo.toml
+        let cargo_toml = dir.join("Cargo.toml");
+        if cargo_toml.exists() {
+            projects.push(dir);
+            // Don't search subdirectories of a Rust project to avoid nested projects
+            continue;
+        }
+
+        // Don't descend past the depth limit
+        if options.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            debug!("Not searching below {}: depth limit reached", dir.display());
+            continue;
+        }
+
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                // Skip directories we can't read
+                debug!("Skipping {}: {}", dir.display(), e);
+                continue;
+            },
+        };
+
+        // Rules from this directory's .gitignore apply to everything below it
+        if options.respect_gitignore {
+            push_gitignore(&dir, &mut state.ignores);
+        }
+
+        let mut subdirectories = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+
+            // Symlinked directories are only descended into when following symlinks
+            let file_type = entry.file_type()?;
+            let is_dir = if file_type.is_symlink() {
+                options.follow_symlinks && path.is_dir()
+            } else {
+                file_type.is_dir()
+            };
+            if !is_dir {
+                continue;
+            }
+
+            // Skip common directories that are unlikely to contain Rust projects, plus any named
+            // with --ignore-dir; .git never holds one even when hidden directories are searched
+            if let Some(name) = path.file_name().and_then(|n| n.to_str())
+                && ((name.starts_with('.') && !options.include_hidden) || name == ".git"
+                    || name == "target" || name == "node_modules" || options.ignore_dirs.iter().any(|dir| dir == name)) {
+                debug!("Skipping {}", path.display());
+                continue;
+            }
+
+            if is_gitignored(&state.ignores, &path) {
+                debug!("Skipping {}: ignored by .gitignore", path.display());
+                continue;
+            }
+
+            // Excluded directories are pruned along with everything below them
+            if let Ok(relative) = path.strip_prefix(root_dir)
+                && options.exclude.is_match(relative) {
+                debug!("Skipping {}: matches an exclude pattern", path.display());
+                continue;
+            }
+
+            subdirectories.push(path);
+        }
+
+        // Pushed in reverse so the first listed directory is searched first
+        let ignore_count = state.ignores.len();
+        pending.extend(subdirectories.into_iter().rev().map(|path| (path, depth + 1, ignore_count)));
+    }
+
+    Ok(())
+}
+
+/// Reads a package's `[package.metadata.vscode]` table, warning about (and ignoring) a table that
+/// doesn't have the expected shape.
+fn read_vscode_metadata(package: &cargo_metadata::Package) -> VscodeMetadata {
+    let Some(table) = package.metadata.get("vscode") else {
+        return VscodeMetadata::default();
+    };
+    match serde_json::from_value(table.clone()) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            warn!("Ignoring invalid [package.metadata.vscode] in {}: {}", package.manifest_path, e);
+            VscodeMetadata::default()
+        }
+    }
+}
+
+/// Combines `--exclude`/`--include` style globs into one matcher.
+pub fn build_globset(patterns: &[Glob]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(pattern.clone());
+    }
+    builder.build()
+}
+
+/// Loads `dir/.gitignore` onto the matcher stack, if there is one.
+fn push_gitignore(dir: &Path, ignores: &mut Vec<Gitignore>) {
+    let gitignore_path = dir.join(".gitignore");
+    if !gitignore_path.is_file() {
+        return;
+    }
+
+    // Match against absolute paths so relative roots like `.` behave the same as absolute ones
+    let gitignore_path = std::path::absolute(&gitignore_path).unwrap_or(gitignore_path);
+    let (gitignore, err) = Gitignore::new(&gitignore_path);
+    if let Some(err) = err {
+        warn!("Problem reading {}: {}", gitignore_path.display(), err);
+    }
+    ignores.push(gitignore);
+}
+
+/// Collects the .gitignore files between the enclosing git repository's root and `root_dir`
+/// (exclusive), outermost first, so rules from a monorepo's top level apply to a nested root.
+fn ancestor_gitignores(root_dir: &Path) -> Vec<Gitignore> {
+    let Ok(root_dir) = std::path::absolute(root_dir) else {
+        return Vec::new();
+    };
+
+    let mut ancestors = Vec::new();
+    for dir in root_dir.ancestors().skip(1) {
+        ancestors.push(dir);
+        if dir.join(".git").exists() {
+            let mut ignores = Vec::new();
+            for dir in ancestors.iter().rev() {
+                push_gitignore(dir, &mut ignores);
+            }
+            return ignores;
+        }
+    }
+
+    // Not inside a git repository, so only .gitignore files under the root apply
+    Vec::new()
+}
+
+fn is_gitignored(ignores: &[Gitignore], dir: &Path) -> bool {
+    let dir = std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+    // The innermost .gitignore with a matching rule decides, as in git
+    for gitignore in ignores.iter().rev() {
+        match gitignore.matched(&dir, true) {
+            Match::Ignore(_) => return true,
+            Match::Whitelist(_) => return false,
+            Match::None => {},
+        }
+    }
+    false
+}
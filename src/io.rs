@@ -0,0 +1,655 @@
+//! Reading, merging and writing the `.code-workspace` file.
+
+use clap::ValueEnum;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+
+use crate::config::{folder_path, GeneratedSections, WorkspaceLaunchConfig, LINKED_PROJECTS_SETTING};
+use crate::{jsonc, Error};
+
+/// Settings that control how the workspace file is written.
+pub struct WriteOptions {
+    /// Print the result instead of touching the file system
+    pub dry_run: bool,
+    /// Print which folders and launch configurations would change instead of touching the file
+    /// system
+    pub diff: bool,
+    /// Copy an existing workspace file aside before overwriting it
+    pub backup: bool,
+    pub max_backups: NonZeroUsize,
+    /// How newly added workspace folders are labelled
+    pub folder_names: FolderNames,
+    /// Base name of the workspace file and its display name; `None` uses the root directory's name
+    pub label: Option<String>,
+    /// Workspace `name` to write; `None` keeps the existing file's name, generating one only for
+    /// a new file
+    pub name: Option<String>,
+    /// Check the result with [`validate_workspace`] and fail instead of writing an invalid file
+    pub validate: bool,
+}
+
+impl WriteOptions {
+    /// Name of the workspace file written for `root_dir`.
+    pub fn workspace_filename(&self, root_dir: &Path) -> String {
+        match &self.label {
+            Some(label) => format!("{}.code-workspace", label),
+            None => generate_workspace_filename(root_dir),
+        }
+    }
+}
+
+/// Label given to the workspace folder of each project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FolderNames {
+    /// No label; VS Code shows the directory name
+    Path,
+    /// The name of the project's root package, when it has one
+    Package,
+}
+
+/// A `.code-workspace` file.
+#[derive(Serialize, Deserialize)]
+pub struct WorkspaceFile {
+    pub folders: Vec<WorkspaceFolder>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settings: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub launch: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tasks: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<serde_json::Value>,
+    /// Any other top-level keys, kept untouched
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// An entry of a workspace file's `folders`, relative to the file.
+#[derive(Serialize, Deserialize)]
+pub struct WorkspaceFolder {
+    pub path: String,
+    /// Label shown in the explorer instead of the directory name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Any other keys set on the folder, kept untouched
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+fn generate_workspace_name(root_dir: &Path, label: Option<&str>, project_paths: &[PathBuf]) -> String {
+    // If only one project, use its name
+    if project_paths.len() == 1
+        && let Some(project_name) = project_paths[0].file_name().and_then(|n| n.to_str()) {
+        return format!("{} (Rust)", project_name);
+    }
+    
+    // For multiple projects, use the root directory name with project count
+    let root_name = label.unwrap_or_else(|| {
+        root_dir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("Rust Projects")
+    });
+    
+    if project_paths.len() > 1 {
+        format!("{} ({} Rust Projects)", root_name, project_paths.len())
+    } else {
+        format!("{} (Rust)", root_name)
+    }
+}
+
+/// Key every written launch configuration carries so a later run knows it owns it, whatever the
+/// configuration is named.
+const GENERATED_BY_KEY: &str = "generatedBy";
+const GENERATED_BY: &str = "rust-vscode-workspace-configurator";
+
+/// Merges generated launch configurations into an existing launch object. Tool-owned
+/// configurations are updated in place (or dropped when their target no longer exists), the
+/// user's own configurations are kept, and new generated configurations are appended.
+fn merge_launch(existing: Option<serde_json::Value>, generated: &WorkspaceLaunchConfig) -> Result<serde_json::Value, serde_json::Error> {
+    let mut generated = serde_json::to_value(generated)?;
+    let mut pending: Vec<serde_json::Value> = generated["configurations"].as_array().cloned().unwrap_or_default();
+    for config in &mut pending {
+        config[GENERATED_BY_KEY] = serde_json::json!(GENERATED_BY);
+    }
+
+    let mut merged = Vec::new();
+    for config in existing.as_ref().and_then(|e| e["configurations"].as_array()).into_iter().flatten() {
+        // A configuration with a generated name, even one from a template, takes its place
+        if let Some(index) = pending.iter().position(|c| c["name"] == config["name"]) {
+            merged.push(pending.remove(index));
+        } else if config[GENERATED_BY_KEY] != GENERATED_BY {
+            merged.push(config.clone());
+        }
+    }
+    merged.extend(pending);
+
+    // Keep any other launch-level keys the user added (such as compounds)
+    let mut result = match existing {
+        Some(serde_json::Value::Object(map)) => serde_json::Value::Object(map),
+        _ => serde_json::json!({}),
+    };
+    result["version"] = generated["version"].take();
+    result["configurations"] = serde_json::Value::Array(merged);
+
+    // Generated compounds replace those with the same name; the user's own compounds stay
+    if let Some(generated_compounds) = generated["compounds"].as_array() {
+        let mut compounds: Vec<serde_json::Value> = result["compounds"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|c| !generated_compounds.iter().any(|g| g["name"] == c["name"]))
+            .cloned()
+            .collect();
+        compounds.extend(generated_compounds.iter().cloned());
+        result["compounds"] = serde_json::Value::Array(compounds);
+    }
+    Ok(result)
+}
+
+/// Merges generated tasks into an existing tasks object, replacing tasks with the same label
+/// and keeping every other (user-defined) task.
+fn merge_tasks(existing: Option<serde_json::Value>, generated: &serde_json::Value) -> serde_json::Value {
+    let generated_tasks = generated["tasks"].as_array().cloned().unwrap_or_default();
+    let generated_labels: Vec<&serde_json::Value> = generated_tasks.iter().map(|t| &t["label"]).collect();
+
+    let mut merged: Vec<serde_json::Value> = existing.as_ref()
+        .and_then(|e| e["tasks"].as_array())
+        .map(|tasks| {
+            tasks.iter()
+                .filter(|t| !generated_labels.contains(&&t["label"]))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // A default build task the user chose stays the only one
+    let user_has_default_build = merged.iter().any(|t| t["group"]["kind"] == "build" && t["group"]["isDefault"] == true);
+    merged.extend(generated_tasks.iter().cloned().map(|mut task| {
+        if user_has_default_build && task["group"]["isDefault"] == true {
+            task["group"] = serde_json::json!("build");
+        }
+        task
+    }));
+
+    let mut result = match existing {
+        Some(serde_json::Value::Object(map)) => serde_json::Value::Object(map),
+        _ => serde_json::json!({}),
+    };
+    result["version"] = generated["version"].clone();
+    result["tasks"] = serde_json::Value::Array(merged);
+    result
+}
+
+/// Adds generated recommendations to an existing extensions object without dropping the user's entries.
+fn merge_extensions(existing: Option<serde_json::Value>, generated: &serde_json::Value) -> serde_json::Value {
+    let mut result = match existing {
+        Some(serde_json::Value::Object(map)) => serde_json::Value::Object(map),
+        _ => serde_json::json!({}),
+    };
+
+    let mut recommendations = result["recommendations"].as_array().cloned().unwrap_or_default();
+    for recommendation in generated["recommendations"].as_array().into_iter().flatten() {
+        if !recommendations.contains(recommendation) {
+            recommendations.push(recommendation.clone());
+        }
+    }
+    result["recommendations"] = serde_json::Value::Array(recommendations);
+    result
+}
+
+/// Merges generated settings into the user's settings. Keys the user already set are kept as they
+/// are; for object-valued settings (such as exclude maps) only the missing entries are added.
+fn merge_settings(existing: Option<serde_json::Value>, generated: &serde_json::Value) -> serde_json::Value {
+    let mut result = match existing {
+        Some(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+
+    for (key, value) in generated.as_object().into_iter().flatten() {
+        match (result.get_mut(key), value) {
+            (None, _) => {
+                result.insert(key.clone(), value.clone());
+            },
+            (Some(_), _) if key == LINKED_PROJECTS_SETTING => {
+                result.insert(key.clone(), value.clone());
+            },
+            (Some(serde_json::Value::Object(current)), serde_json::Value::Object(entries)) => {
+                for (entry_key, entry_value) in entries {
+                    current.entry(entry_key.clone()).or_insert_with(|| entry_value.clone());
+                }
+            },
+            // The user chose their own value for this setting
+            (Some(_), _) => {},
+        }
+    }
+
+    serde_json::Value::Object(result)
+}
+
+/// Name of the workspace file generated for `root_dir`: `<directory name>.code-workspace`.
+pub fn generate_workspace_filename(root_dir: &Path) -> String {
+    let root_name = root_dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("rust-projects");
+    
+    format!("{}.code-workspace", root_name)
+}
+
+/// Lists the backups of a workspace file, oldest first. The plain `.backup` file is the oldest,
+/// followed by `.backup.1`, `.backup.2` and so on.
+fn find_backups(output_dir: &Path, base_backup_name: &str) -> std::io::Result<Vec<(usize, PathBuf)>> {
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(output_dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(file_name) = file_name.to_str() else {
+            continue;
+        };
+        let counter = if file_name == base_backup_name {
+            Some(0)
+        } else {
+            file_name
+                .strip_prefix(base_backup_name)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .and_then(|suffix| suffix.parse::<usize>().ok())
+        };
+        if let Some(counter) = counter {
+            backups.push((counter, entry.path()));
+        }
+    }
+    backups.sort();
+    Ok(backups)
+}
+
+/// Merges the generated sections into the workspace file in `output_dir` (creating it if needed)
+/// with a folder for each of `projects`, then writes it, or prints it for a dry run.
+pub fn write_workspace_launch_config(output_dir: &Path, sections: &GeneratedSections, projects: &[PathBuf], root_dir: &Path, options: &WriteOptions) -> Result<(), Error> {
+    let workspace_filename = options.workspace_filename(root_dir);
+    let workspace_path = output_dir.join(&workspace_filename);
+    
+    // The existing file's text and parsed content, used to edit it in place
+    let mut original: Option<(String, serde_json::Value)> = None;
+    
+    let exists = workspace_path.exists();
+    let mut workspace_file = if exists {
+        // Read existing workspace file
+        let content = fs::read_to_string(&workspace_path)?;
+        
+        // Workspace files are JSONC: comments and trailing commas are allowed
+        let parsed = jsonc::parse(&content)
+            .and_then(|value| serde_json::from_value::<WorkspaceFile>(value.clone()).map(|workspace| (value, workspace)));
+        match parsed {
+            Ok((value, workspace)) => {
+                original = Some((content, value));
+                workspace
+            },
+            Err(e) => {
+                warn!("Failed to parse existing workspace file: {}; creating a new workspace file instead", e);
+                // Create new workspace file with basic structure
+                WorkspaceFile {
+                    folders: vec![],
+                    name: None,
+                    settings: None,
+                    launch: None,
+                    tasks: None,
+                    extensions: None,
+                    other: serde_json::Map::new(),
+                }
+            }
+        }
+    } else {
+        // Create new workspace file with basic structure
+        WorkspaceFile {
+            folders: vec![],
+            name: None,
+            settings: None,
+            launch: None,
+            tasks: None,
+            extensions: None,
+            other: serde_json::Map::new(),
+        }
+    };
+    
+    // Every discovered project gets a folder, whether or not it has runnables
+    let mut project_paths: Vec<PathBuf> = projects.to_vec();
+    project_paths.sort();
+    project_paths.dedup();
+    
+    // Keep a name the user chose unless --name replaces it; only a new file gets a generated one
+    workspace_file.name = match (&options.name, workspace_file.name.take()) {
+        (Some(name), _) => Some(name.clone()),
+        (None, Some(existing)) => Some(existing),
+        (None, None) => Some(generate_workspace_name(root_dir, options.label.as_deref(), &project_paths)),
+    };
+    
+    // Create folders for all discovered projects
+    let mut folders = Vec::new();
+    for project_path in &project_paths {
+        let name = match options.folder_names {
+            FolderNames::Path => None,
+            FolderNames::Package => root_package_name(project_path),
+        };
+        
+        folders.push(WorkspaceFolder {
+            path: folder_path(project_path, root_dir),
+            name,
+            other: serde_json::Map::new(),
+        });
+    }
+    
+    // If no projects found or only root project, add current directory
+    if folders.is_empty() {
+        folders.push(WorkspaceFolder {
+            path: ".".to_string(),
+            name: None,
+            other: serde_json::Map::new(),
+        });
+    }
+    
+    folders.sort_by(|a, b| a.path.cmp(&b.path));
+    workspace_file.folders = merge_folders(std::mem::take(&mut workspace_file.folders), folders, output_dir);
+    
+    // Clean up null/empty fields to follow VS Code conventions
+    if workspace_file.settings.as_ref().is_some_and(|s| s.is_null()) {
+        workspace_file.settings = None;
+    }
+    if workspace_file.tasks.as_ref().is_some_and(|t| t.is_null()) {
+        workspace_file.tasks = None;
+    }
+    if workspace_file.extensions.as_ref().is_some_and(|e| e.is_null() || (e.is_object() && e.as_object().unwrap().is_empty())) {
+        workspace_file.extensions = None;
+    }
+    
+    // Update the generated sections, keeping user additions in tasks and extensions
+    workspace_file.launch = Some(merge_launch(workspace_file.launch.take(), &sections.launch)?);
+    workspace_file.tasks = Some(merge_tasks(workspace_file.tasks.take(), &sections.tasks));
+    workspace_file.extensions = Some(merge_extensions(workspace_file.extensions.take(), &sections.extensions));
+    workspace_file.settings = Some(merge_settings(workspace_file.settings.take(), &sections.settings));
+    
+    // Write back to file, editing the existing text in place so comments and formatting survive
+    let updated = serde_json::to_value(&workspace_file)?;
+    if options.validate {
+        let problems = validate_workspace(&updated);
+        if !problems.is_empty() {
+            return Err(Error::InvalidWorkspace { problems });
+        }
+    }
+    if options.diff {
+        let changes = describe_changes(original.as_ref().map(|(_, value)| value), &updated);
+        if changes.is_empty() {
+            println!("No changes to folders or launch configurations");
+        }
+        for change in changes {
+            println!("{}", change);
+        }
+        return Ok(());
+    }
+    // Printed output goes to other tools, so it's plain JSON; only the file itself keeps comments
+    if options.dry_run {
+        println!("{}", serde_json::to_string_pretty(&updated)?);
+        return Ok(());
+    }
+    let json_content = match original.and_then(|(content, value)| jsonc::patch(&content, &value, &updated)) {
+        Some(patched) => patched,
+        None => serde_json::to_string_pretty(&updated)?,
+    };
+
+    // Fail before a backup is made when the write can't succeed anyway
+    check_writable(&workspace_path)?;
+
+    // Back up the existing workspace file, but only prune old backups once the new file is in place
+    let base_backup_name = format!("{}.backup", workspace_filename);
+    let existing_backups = if exists && options.backup { find_backups(output_dir, &base_backup_name)? } else { Vec::new() };
+    let backup_path = if exists && options.backup {
+        // Number past the newest backup so the suffix keeps growing with age even after pruning
+        let backup_path = match existing_backups.last() {
+            Some((counter, _)) => output_dir.join(format!("{}.{}", base_backup_name, counter + 1)),
+            None => output_dir.join(&base_backup_name),
+        };
+        fs::copy(&workspace_path, &backup_path).map_err(|e| write_error(&backup_path, e))?;
+        info!("Backed up existing workspace file to {}", backup_path.display());
+        Some(backup_path)
+    } else {
+        None
+    };
+
+    if let Err(e) = write_atomically(&workspace_path, json_content.as_bytes()) {
+        // The old file is untouched, so its backup would only be clutter
+        if let Some(backup_path) = &backup_path {
+            let _ = fs::remove_file(backup_path);
+        }
+        return Err(write_error(&workspace_path, e));
+    }
+
+    if backup_path.is_some() {
+        // Keep the new backup plus the newest older ones, up to the limit
+        let excess = (existing_backups.len() + 1).saturating_sub(options.max_backups.get());
+        for (_, old_backup) in existing_backups.iter().take(excess) {
+            fs::remove_file(old_backup)?;
+            info!("Removed old backup {}", old_backup.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Reports a read-only workspace file (such as a file checked in read-only under Perforce) as
+/// [`Error::PermissionDenied`].
+///
+/// Only the file is checked: Windows sets the read-only attribute on directories for its own
+/// purposes without it blocking writes, so a directory that really can't be written to is left
+/// to fail the write itself, which [`write_error`] reports the same way.
+fn check_writable(workspace_path: &Path) -> Result<(), Error> {
+    if fs::metadata(workspace_path).is_ok_and(|metadata| metadata.permissions().readonly()) {
+        return Err(Error::PermissionDenied { path: workspace_path.to_path_buf() });
+    }
+    Ok(())
+}
+
+/// Turns a failure to write `path` into an error naming it when permissions are the cause.
+fn write_error(path: &Path, error: std::io::Error) -> Error {
+    match error.kind() {
+        std::io::ErrorKind::PermissionDenied => Error::PermissionDenied { path: path.to_path_buf() },
+        _ => Error::Io(error),
+    }
+}
+
+/// Checks a workspace file against the parts of VS Code's workspace, launch and tasks schemas that
+/// the editor relies on, returning a description of every problem found. VS Code silently ignores
+/// launch configurations it can't make sense of, so these mistakes would otherwise go unnoticed.
+pub fn validate_workspace(workspace: &serde_json::Value) -> Vec<String> {
+    let mut problems = Vec::new();
+    let Some(workspace) = workspace.as_object() else {
+        return vec!["the workspace is not a JSON object".to_string()];
+    };
+    let non_empty_string = |value: Option<&serde_json::Value>| value.and_then(|v| v.as_str()).is_some_and(|s| !s.is_empty());
+
+    match workspace.get("folders").and_then(|f| f.as_array()) {
+        Some(folders) => {
+            for (index, folder) in folders.iter().enumerate() {
+                if !non_empty_string(folder.get("path")) {
+                    problems.push(format!("folders[{}] has no \"path\"", index));
+                }
+            }
+        },
+        None => problems.push("\"folders\" is missing or not an array".to_string()),
+    }
+
+    let mut task_labels = HashSet::new();
+    if let Some(tasks) = workspace.get("tasks") {
+        match tasks.get("tasks").and_then(|t| t.as_array()) {
+            Some(tasks) => {
+                for (index, task) in tasks.iter().enumerate() {
+                    match task.get("label").and_then(|l| l.as_str()) {
+                        Some(label) if !label.is_empty() => {
+                            task_labels.insert(label);
+                        },
+                        _ => problems.push(format!("tasks.tasks[{}] has no \"label\"", index)),
+                    }
+                    // A task that only runs other tasks through `dependsOn` needs no type
+                    if !non_empty_string(task.get("type")) && task.get("command").is_some() {
+                        problems.push(format!("tasks.tasks[{}] has no \"type\"", index));
+                    }
+                }
+            },
+            None => problems.push("\"tasks.tasks\" is missing or not an array".to_string()),
+        }
+    }
+
+    if let Some(launch) = workspace.get("launch") {
+        if !non_empty_string(launch.get("version")) {
+            problems.push("\"launch.version\" is missing".to_string());
+        }
+        let mut names = HashSet::new();
+        match launch.get("configurations").and_then(|c| c.as_array()) {
+            Some(configurations) => {
+                for (index, config) in configurations.iter().enumerate() {
+                    let Some(name) = config.get("name").and_then(|n| n.as_str()).filter(|n| !n.is_empty()) else {
+                        problems.push(format!("launch.configurations[{}] has no \"name\"", index));
+                        continue;
+                    };
+                    if !names.insert(name) {
+                        problems.push(format!("configuration '{}' appears more than once", name));
+                    }
+                    if !non_empty_string(config.get("type")) {
+                        problems.push(format!("configuration '{}' has no \"type\"", name));
+                    }
+                    match config.get("request").and_then(|r| r.as_str()) {
+                        Some("launch" | "attach") => {},
+                        _ => problems.push(format!("configuration '{}' has a \"request\" other than \"launch\" or \"attach\"", name)),
+                    }
+                    if let Some(task) = config.get("preLaunchTask").and_then(|t| t.as_str())
+                        && !task_labels.contains(task) {
+                        problems.push(format!("configuration '{}' runs the undefined task '{}'", name, task));
+                    }
+                }
+            },
+            None => problems.push("\"launch.configurations\" is missing or not an array".to_string()),
+        }
+        for compound in launch.get("compounds").and_then(|c| c.as_array()).into_iter().flatten() {
+            let compound_name = compound.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+            for member in compound.get("configurations").and_then(|c| c.as_array()).into_iter().flatten() {
+                if member.as_str().is_none_or(|member| !names.contains(member)) {
+                    problems.push(format!("compound '{}' starts the undefined configuration {}", compound_name, member));
+                }
+            }
+        }
+    }
+
+    if let Some(recommendations) = workspace.get("extensions").and_then(|e| e.get("recommendations"))
+        && !recommendations.as_array().is_some_and(|r| r.iter().all(|id| id.is_string())) {
+        problems.push("\"extensions.recommendations\" is not a list of extension IDs".to_string());
+    }
+
+    problems
+}
+
+/// Lists the folders and launch configurations that differ between two workspace files, one per
+/// line: `+` for added, `-` for removed and `~` for changed entries.
+fn describe_changes(old: Option<&serde_json::Value>, new: &serde_json::Value) -> Vec<String> {
+    let entries = |workspace: Option<&serde_json::Value>, pointer: &str, key: &str| -> Vec<(String, serde_json::Value)> {
+        workspace
+            .and_then(|w| w.pointer(pointer))
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| Some((entry.get(key)?.as_str()?.to_string(), entry.clone())))
+            .collect()
+    };
+
+    let mut changes = Vec::new();
+    for (label, pointer, key) in [("folder", "/folders", "path"), ("configuration", "/launch/configurations", "name")] {
+        let before = entries(old, pointer, key);
+        let after = entries(Some(new), pointer, key);
+        for (id, value) in &after {
+            match before.iter().find(|(old_id, _)| old_id == id) {
+                None => changes.push(format!("+ {} {}", label, id)),
+                Some((_, old_value)) if old_value != value => changes.push(format!("~ {} {}", label, id)),
+                Some(_) => {},
+            }
+        }
+        for (id, _) in &before {
+            if !after.iter().any(|(new_id, _)| new_id == id) {
+                changes.push(format!("- {} {}", label, id));
+            }
+        }
+    }
+    changes
+}
+
+/// Reads the `[package] name` of a project's manifest; virtual workspace manifests have none.
+fn root_package_name(project_path: &Path) -> Option<String> {
+    let manifest = fs::read_to_string(project_path.join("Cargo.toml")).ok()?.parse::<toml::Table>().ok()?;
+    manifest.get("package")?.get("name")?.as_str().map(str::to_string)
+}
+
+/// Keeps the folders already in the workspace file, in the user's order and with any name or other
+/// settings they were given, then appends newly discovered ones. Folders added by hand (docs,
+/// scripts, ...) stay; only folders whose directory no longer exists below `base` are dropped.
+fn merge_folders(existing: Vec<WorkspaceFolder>, discovered: Vec<WorkspaceFolder>, base: &Path) -> Vec<WorkspaceFolder> {
+    // "app", "./app" and "./app/" all name the same folder
+    let normalize = |path: &str| {
+        let path = path.trim_end_matches(['/', '\\']);
+        match path.strip_prefix("./").or_else(|| path.strip_prefix(".\\")) {
+            Some(rest) if !rest.is_empty() => rest.to_string(),
+            _ if path.is_empty() => ".".to_string(),
+            _ => path.to_string(),
+        }
+    };
+
+    let discovered_paths: HashSet<String> = discovered.iter().map(|f| normalize(&f.path)).collect();
+    let mut merged: Vec<WorkspaceFolder> = existing.into_iter()
+        .filter(|folder| discovered_paths.contains(&normalize(&folder.path)) || base.join(&folder.path).is_dir())
+        .collect();
+    let kept_paths: HashSet<String> = merged.iter().map(|f| normalize(&f.path)).collect();
+    merged.extend(discovered.into_iter().filter(|folder| !kept_paths.contains(&normalize(&folder.path))));
+    merged
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it over `path`, so an
+/// interrupted run leaves either the previous file or the complete new one, never a truncated file.
+fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("workspace");
+    let temp_path = path.with_file_name(format!(".{}.tmp-{}", file_name, std::process::id()));
+
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        drop(file);
+        rename_over(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+#[cfg(not(windows))]
+fn rename_over(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::rename(from, to)
+}
+
+/// Renaming onto an existing file can fail on Windows (for example on some network shares), so
+/// retry after removing the target.
+#[cfg(windows)]
+fn rename_over(from: &Path, to: &Path) -> std::io::Result<()> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(_) if to.exists() => {
+            fs::remove_file(to)?;
+            fs::rename(from, to)
+        },
+        Err(e) => Err(e),
+    }
+}
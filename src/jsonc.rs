@@ -0,0 +1,311 @@
+//! Minimal support for the JSON-with-comments format used by `.code-workspace` files.
+//!
+//! VS Code accepts `//` and `/* */` comments and trailing commas in workspace files. Instead of
+//! round-tripping such a file through `serde_json` (which would drop every comment), the file is
+//! edited in place: only the values that actually changed are rewritten, everything else is kept
+//! byte for byte.
+
+use serde_json::Value;
+
+/// Parses JSONC text into a JSON value, ignoring comments and trailing commas.
+pub fn parse(text: &str) -> serde_json::Result<Value> {
+    let blanked = blank_comments_and_trailing_commas(text);
+    serde_json::from_slice(&blanked)
+}
+
+/// Rewrites `original` (whose parsed content is `old`) so that it represents `new`, keeping
+/// comments, formatting and key order of everything that did not change.
+///
+/// Returns `None` when the text cannot be edited safely; the caller should then write `new` from
+/// scratch.
+pub fn patch(original: &str, old: &Value, new: &Value) -> Option<String> {
+    let blanked = blank_comments_and_trailing_commas(original);
+    let mut position = 0;
+    skip_whitespace(&blanked, &mut position);
+    let end = value_end(&blanked, position)?;
+
+    let mut edits = Vec::new();
+    patch_value(original, &blanked, position, end, old, new, &mut edits)?;
+
+    // Apply from the back so earlier offsets stay valid
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.start));
+    let mut patched = original.to_string();
+    for edit in edits {
+        patched.replace_range(edit.start..edit.end, &edit.text);
+    }
+
+    // Never write something that does not round-trip to the intended content
+    match parse(&patched) {
+        Ok(reparsed) if reparsed == *new => Some(patched),
+        _ => None,
+    }
+}
+
+struct Edit {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+struct Member {
+    key: String,
+    key_start: usize,
+    value_start: usize,
+    value_end: usize,
+}
+
+fn patch_value(original: &str, blanked: &[u8], start: usize, end: usize, old: &Value, new: &Value, edits: &mut Vec<Edit>) -> Option<()> {
+    if old == new {
+        return Some(());
+    }
+
+    match (old, new) {
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            let (members, close) = object_members(blanked, start)?;
+            let indent = member_indent(original, start, members.first().map(|m| m.key_start));
+
+            for member in &members {
+                if let (Some(old_value), Some(new_value)) = (old_map.get(&member.key), new_map.get(&member.key)) {
+                    patch_value(original, blanked, member.value_start, member.value_end, old_value, new_value, edits)?;
+                }
+            }
+
+            let kept: Vec<bool> = members.iter().map(|m| new_map.contains_key(&m.key)).collect();
+            let spans: Vec<(usize, usize)> = members.iter().map(|m| (m.key_start, m.value_end)).collect();
+            remove_entries(&spans, &kept, close, edits);
+
+            let additions: Vec<String> = new_map
+                .iter()
+                .filter(|(key, _)| !members.iter().any(|m| &m.key == *key))
+                .map(|(key, value)| format!("{}: {}", serde_json::to_string(key).unwrap(), pretty(value, &indent)))
+                .collect();
+            append_entries(original, start, &spans, &kept, &indent, &additions, edits);
+        },
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            let (elements, close) = array_elements(blanked, start)?;
+            let indent = member_indent(original, start, elements.first().map(|e| e.0));
+
+            for ((element_start, element_end), (old_item, new_item)) in elements.iter().zip(old_items.iter().zip(new_items)) {
+                patch_value(original, blanked, *element_start, *element_end, old_item, new_item, edits)?;
+            }
+
+            let kept: Vec<bool> = (0..elements.len()).map(|i| i < new_items.len()).collect();
+            remove_entries(&elements, &kept, close, edits);
+
+            let additions: Vec<String> = new_items.iter().skip(elements.len()).map(|item| pretty(item, &indent)).collect();
+            append_entries(original, start, &elements, &kept, &indent, &additions, edits);
+        },
+        _ => {
+            edits.push(Edit { start, end, text: pretty(new, &line_indent(original, start)) });
+        },
+    }
+
+    Some(())
+}
+
+/// Queues the removal of every entry that is not kept, together with the comma that separated it
+/// from its predecessor.
+fn remove_entries(spans: &[(usize, usize)], kept: &[bool], close: usize, edits: &mut Vec<Edit>) {
+    let mut any_kept_before = false;
+    for (index, &(start, end)) in spans.iter().enumerate() {
+        if kept[index] {
+            any_kept_before = true;
+            continue;
+        }
+        let (start, end) = if any_kept_before {
+            (spans[index - 1].1, end)
+        } else {
+            (start, spans.get(index + 1).map_or(close, |next| next.0))
+        };
+        edits.push(Edit { start, end, text: String::new() });
+    }
+}
+
+/// Queues new entries after the last kept entry, or right after the opening bracket when none is
+/// kept.
+fn append_entries(original: &str, open: usize, spans: &[(usize, usize)], kept: &[bool], indent: &str, additions: &[String], edits: &mut Vec<Edit>) {
+    if additions.is_empty() {
+        return;
+    }
+
+    let separator = format!(",\n{}", indent);
+    let joined = additions.join(&separator);
+    let closing_indent = line_indent(original, open);
+    let last_kept = spans.iter().zip(kept).rev().find(|(_, kept)| **kept);
+    let (position, text) = match (last_kept, spans.first()) {
+        (Some((&(_, end), _)), _) => (end, format!("{}{}", separator, joined)),
+        // Everything is being removed: take the place of the first entry, keeping its indentation
+        (None, Some(&(start, _))) => (start, format!("{}\n{}", joined, closing_indent)),
+        (None, None) => (open + 1, format!("\n{}{}\n{}", indent, joined, closing_indent)),
+    };
+    edits.push(Edit { start: position, end: position, text });
+}
+
+/// Pretty-prints `value` so that its continuation lines line up under `indent`.
+fn pretty(value: &Value, indent: &str) -> String {
+    serde_json::to_string_pretty(value)
+        .unwrap()
+        .replace('\n', &format!("\n{}", indent))
+}
+
+/// The whitespace at the start of the line containing `position`.
+fn line_indent(text: &str, position: usize) -> String {
+    let line_start = text[..position].rfind('\n').map_or(0, |i| i + 1);
+    text[line_start..].chars().take_while(|c| *c == ' ' || *c == '\t').collect()
+}
+
+/// The indentation used for entries of the container opened at `open`: that of its first entry
+/// when it has one, otherwise one level deeper than the container itself.
+fn member_indent(text: &str, open: usize, first_entry: Option<usize>) -> String {
+    match first_entry {
+        Some(position) if text[open..position].contains('\n') => line_indent(text, position),
+        _ => format!("{}  ", line_indent(text, open)),
+    }
+}
+
+fn object_members(text: &[u8], open: usize) -> Option<(Vec<Member>, usize)> {
+    let mut members = Vec::new();
+    let mut position = open + 1;
+    loop {
+        skip_whitespace(text, &mut position);
+        match text.get(position)? {
+            b'}' => return Some((members, position)),
+            b',' => position += 1,
+            b'"' => {
+                let key_start = position;
+                let key_end = string_end(text, position)?;
+                let key = serde_json::from_slice(&text[key_start..key_end]).ok()?;
+                position = key_end;
+                skip_whitespace(text, &mut position);
+                if text.get(position) != Some(&b':') {
+                    return None;
+                }
+                position += 1;
+                skip_whitespace(text, &mut position);
+                let value_start = position;
+                let value_end = value_end(text, position)?;
+                members.push(Member { key, key_start, value_start, value_end });
+                position = value_end;
+            },
+            _ => return None,
+        }
+    }
+}
+
+fn array_elements(text: &[u8], open: usize) -> Option<(Vec<(usize, usize)>, usize)> {
+    let mut elements = Vec::new();
+    let mut position = open + 1;
+    loop {
+        skip_whitespace(text, &mut position);
+        match text.get(position)? {
+            b']' => return Some((elements, position)),
+            b',' => position += 1,
+            _ => {
+                let end = value_end(text, position)?;
+                elements.push((position, end));
+                position = end;
+            },
+        }
+    }
+}
+
+/// Finds the end of the value starting at `start` in comment-free text.
+fn value_end(text: &[u8], start: usize) -> Option<usize> {
+    match text.get(start)? {
+        b'"' => string_end(text, start),
+        b'{' | b'[' => {
+            let mut depth = 0;
+            let mut position = start;
+            while position < text.len() {
+                match text[position] {
+                    b'"' => {
+                        position = string_end(text, position)?;
+                        continue;
+                    },
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(position + 1);
+                        }
+                    },
+                    _ => {},
+                }
+                position += 1;
+            }
+            None
+        },
+        _ => {
+            let length = text[start..]
+                .iter()
+                .position(|b| matches!(b, b',' | b'}' | b']') || b.is_ascii_whitespace())
+                .unwrap_or(text.len() - start);
+            Some(start + length)
+        },
+    }
+}
+
+/// Returns the position just past the closing quote of the string starting at `start`.
+fn string_end(text: &[u8], start: usize) -> Option<usize> {
+    let mut position = start + 1;
+    while position < text.len() {
+        match text[position] {
+            b'\\' => position += 2,
+            b'"' => return Some(position + 1),
+            _ => position += 1,
+        }
+    }
+    None
+}
+
+fn skip_whitespace(text: &[u8], position: &mut usize) {
+    while *position < text.len() && text[*position].is_ascii_whitespace() {
+        *position += 1;
+    }
+}
+
+/// Replaces comments and trailing commas with spaces. Every byte keeps its offset, so positions
+/// found in the result are valid in the original text.
+fn blank_comments_and_trailing_commas(text: &str) -> Vec<u8> {
+    let mut bytes = text.as_bytes().to_vec();
+    let mut position = 0;
+    let mut pending_comma: Option<usize> = None;
+
+    while position < bytes.len() {
+        match bytes[position] {
+            b'"' => {
+                pending_comma = None;
+                position = string_end(&bytes, position).unwrap_or(bytes.len());
+                continue;
+            },
+            b'/' if bytes.get(position + 1) == Some(&b'/') => {
+                while position < bytes.len() && bytes[position] != b'\n' {
+                    bytes[position] = b' ';
+                    position += 1;
+                }
+                continue;
+            },
+            b'/' if bytes.get(position + 1) == Some(&b'*') => {
+                let end = text[position + 2..].find("*/").map_or(bytes.len(), |i| position + 2 + i + 2);
+                for byte in &mut bytes[position..end] {
+                    if *byte != b'\n' {
+                        *byte = b' ';
+                    }
+                }
+                position = end;
+                continue;
+            },
+            b',' => pending_comma = Some(position),
+            b'}' | b']' => {
+                if let Some(comma) = pending_comma.take() {
+                    bytes[comma] = b' ';
+                }
+            },
+            byte if byte.is_ascii_whitespace() => {},
+            _ => pending_comma = None,
+        }
+        position += 1;
+    }
+
+    bytes
+}
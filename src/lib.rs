@@ -0,0 +1,54 @@
+//! Discovers the Rust projects below a directory and generates VS Code multi-root workspace
+//! configuration for them: workspace folders, debugger launch configurations, cargo tasks,
+//! extension recommendations and rust-analyzer settings.
+//!
+//! The `rust-vscode-workspace-configurator` binary is a thin command-line wrapper around these
+//! functions; other tools can call them directly and post-process the results before writing.
+
+use std::path::PathBuf;
+
+mod config;
+mod discovery;
+mod io;
+mod jsonc;
+
+pub use config::{
+    CargoConfig, CargoFilter, Compound, Configuration, ConfigurationTemplate, Debugger,
+    EnvironmentEntry, GeneratedSections, LaunchConfig, LaunchOptions, Profile, RemoteTarget,
+    Terminal, WorkspaceLaunchConfig, detect_default_debugger, generate_extensions,
+    generate_launch_config, generate_settings, generate_workspace_launch_config,
+    generate_workspace_tasks,
+};
+pub use discovery::{
+    Discovery, DiscoveryFeatures, DiscoveryOptions, Runnable, RunnableType, TestTarget,
+    VscodeOverrides, build_globset, discover_runnables, discover_runnables_in_roots, find_projects,
+    is_wasm_target,
+};
+pub use io::{
+    FolderNames, WorkspaceFile, WorkspaceFolder, WriteOptions, generate_workspace_filename,
+    validate_workspace, write_workspace_launch_config,
+};
+
+/// Errors returned by the public functions of this crate.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Discovery found no `Cargo.toml` at or below the root directory.
+    #[error("No Rust projects (Cargo.toml files) found in {}", root.display())]
+    NoProjectsFound { root: PathBuf },
+    /// `cargo metadata` failed for a project's manifest.
+    #[error("Failed to read metadata for {}: {source}", path.display())]
+    MetadataFailed { path: PathBuf, source: cargo_metadata::Error },
+    /// The workspace file about to be written doesn't have the shape VS Code expects.
+    #[error("The generated workspace is invalid:\n  {}", problems.join("\n  "))]
+    InvalidWorkspace { problems: Vec<String> },
+    /// The workspace file or its directory can't be written to.
+    #[error("Cannot write to {}: permission denied (make it writable, e.g. by checking it out for editing, and run again)", path.display())]
+    PermissionDenied { path: PathBuf },
+    /// A `--template` file can't be turned into launch configurations.
+    #[error("Invalid launch configuration template: {message}")]
+    InvalidTemplate { message: String },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Serialization(#[from] serde_json::Error),
+}
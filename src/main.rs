@@ -1,87 +1,309 @@
-use cargo_metadata::{CargoOpt, MetadataCommand, TargetKind};
-use clap::Parser;
-use regex::Regex;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use globset::Glob;
+use log::{error, info, warn, Level, LevelFilter};
+use rust_vscode_workspace_configurator::{
+    build_globset, detect_default_debugger, discover_runnables_in_roots, generate_extensions, generate_settings,
+    generate_workspace_launch_config, generate_workspace_tasks, is_wasm_target, ConfigurationTemplate,
+    write_workspace_launch_config, Debugger, Discovery, DiscoveryFeatures, DiscoveryOptions, Error, FolderNames, GeneratedSections,
+    LaunchOptions, Profile, RemoteTarget, Runnable, RunnableType, Terminal, TestTarget, WriteOptions,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 #[derive(Parser)]
 #[command(name = "rust-vscode-workspace-configurator")]
 #[command(about = "Generate VS Code multi-root workspace configurations for all discovered Rust projects")]
 struct Args {
-    /// Root directory to search for Rust projects (defaults to current directory)
-    #[arg(short, long)]
-    root: Option<PathBuf>,
-}
+    /// Root directory to search for Rust projects (defaults to current directory); repeat to
+    /// combine several directories into one workspace
+    #[arg(short, long = "root", value_name = "ROOT")]
+    roots: Vec<PathBuf>,
+
+    /// Check the generated workspace against the shape VS Code expects and fail instead of writing it
+    #[arg(long)]
+    validate: bool,
+
+    /// Fail without writing anything when a project's metadata can't be read
+    #[arg(long)]
+    strict: bool,
+
+    /// Exit successfully, writing nothing, when no Cargo.toml is found at all
+    #[arg(long)]
+    allow_empty: bool,
+
+    /// Generate configurations for exactly this Cargo.toml, without searching any directory
+    #[arg(long, value_name = "FILE", conflicts_with = "roots")]
+    manifest_path: Option<PathBuf>,
+
+    /// Print the generated workspace JSON to stdout instead of writing it (no backup is created)
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Show which folders and launch configurations would be added, removed or changed, without
+    /// writing anything
+    #[arg(long, conflicts_with_all = ["dry_run", "stdout"])]
+    diff: bool,
+
+    /// Write the workspace JSON to stdout for piping into other tools; progress goes to stderr
+    #[arg(long)]
+    stdout: bool,
+
+    /// Only print warnings and errors (and the workspace JSON of a dry run)
+    #[arg(long, short, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Also print why directories were skipped and how long cargo metadata took; repeat for more
+    #[arg(long, short, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Overwrite an existing workspace file without first copying it to a .backup file
+    #[arg(long)]
+    no_backup: bool,
+
+    /// How many backups of the workspace file to keep; the oldest are deleted first
+    #[arg(long, value_name = "N", default_value = "5")]
+    max_backups: NonZeroUsize,
+
+    /// Label for each project's workspace folder: the root package's name, or none so VS Code shows the directory name
+    #[arg(long, value_enum, value_name = "STRATEGY", default_value = "package")]
+    folder_names: FolderNames,
+
+    /// Display name of the workspace; without it an existing file keeps its name
+    #[arg(long, value_name = "NAME")]
+    name: Option<String>,
 
-#[derive(Debug, Clone)]
-struct Runnable {
-    name: String,
-    package: String,
-    runnable_type: RunnableType,
-    required_features: Vec<String>,
-    project_path: PathBuf,
-}
+    /// Debugger backend the generated launch configurations target
+    /// (defaults to cppvsdbg on an MSVC host and lldb everywhere else)
+    #[arg(long, value_enum)]
+    debugger: Option<Debugger>,
 
-#[derive(Debug, Clone)]
-enum RunnableType {
-    Binary,
-    Example,
-}
+    /// GDB executable written to miDebuggerPath of gdb configurations (defaults to 'gdb' from PATH)
+    #[arg(long, value_name = "PATH")]
+    gdb_path: Option<String>,
 
-#[derive(Serialize, Deserialize)]
-struct LaunchConfig {
-    version: String,
-    configurations: Vec<Configuration>,
-}
+    /// Debug adapter `type` to write into every launch configuration verbatim, e.g. 'lldb-vscode'
+    /// (the rest of the configuration still follows --debugger)
+    #[arg(long, value_name = "TYPE", value_parser = parse_config_type)]
+    config_type: Option<String>,
 
-#[derive(Serialize, Deserialize, Clone)]
-struct Configuration {
-    name: String,
-    #[serde(rename = "type")]
-    config_type: String,
-    request: String,
-    cwd: String,
-    env: EnvVars,
-    cargo: CargoConfig,
-    args: Vec<String>,
-}
+    /// Environment variable to set in every launch configuration (repeatable)
+    #[arg(long = "env", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+    env: Vec<(String, String)>,
 
-#[derive(Serialize, Deserialize, Clone)]
-struct EnvVars {
-    #[serde(rename = "BEVY_ASSET_ROOT")]
-    bevy_asset_root: String,
-}
+    /// RUST_BACKTRACE value set in every launch configuration, unless --env or the package's metadata sets it
+    #[arg(long, value_name = "MODE", value_parser = ["0", "1", "full"])]
+    backtrace: Option<String>,
+
+    /// RUST_LOG filter set in every launch configuration, unless --env or the package's metadata sets it
+    #[arg(long, value_name = "FILTER")]
+    rust_log: Option<String>,
+
+    /// Set each launch configuration's preLaunchTask to the package's generated cargo build task
+    #[arg(long)]
+    pre_launch_build: bool,
+
+    /// Append each target's required features to its configuration names, e.g. "Debug binary 'app::app' [gpu]"
+    #[arg(long)]
+    name_features: bool,
 
-#[derive(Serialize, Deserialize, Clone)]
-struct CargoConfig {
-    args: Vec<String>,
+    /// Write configuration names literally instead of trimming them and collapsing whitespace
+    #[arg(long)]
+    raw_names: bool,
+
+    /// Have CodeLLDB launch built binaries and examples directly instead of running cargo on
+    /// every launch (combine with --pre-launch-build to build first)
+    #[arg(long)]
+    program_mode: bool,
+
+    /// Break at the program entry point when a debug session starts
+    #[arg(long)]
+    stop_at_entry: bool,
+
+    /// Where CodeLLDB sends the program's input and output (omitted unless given)
+    #[arg(long, value_enum)]
+    terminal: Option<Terminal>,
+
+    /// Dotenv file every launch configuration loads its environment from (`envFile`)
+    #[arg(long, value_name = "PATH")]
+    env_file: Option<PathBuf>,
+
+    /// LLDB command CodeLLDB or lldb-dap runs before creating the debug target (repeatable)
+    #[arg(long = "init-command", value_name = "CMD")]
+    init_commands: Vec<String>,
+
+    /// CodeLLDB or lldb-dap source path remapping from a build-time path to a local one (repeatable)
+    #[arg(long = "source-map", value_name = "FROM=TO", value_parser = parse_key_value)]
+    source_map: Vec<(String, String)>,
+
+    /// Also generate a configuration attaching to a running instance of each binary
+    #[arg(long)]
+    with_attach: bool,
+
+    /// Host of a debug server on a remote device to generate binary configurations for
+    #[arg(long, value_name = "HOST", requires = "remote_port")]
+    remote_host: Option<String>,
+
+    /// Port of the remote debug server
+    #[arg(long, value_name = "PORT", requires = "remote_host")]
+    remote_port: Option<u16>,
+
+    /// JSON file of one launch configuration with `{{package}}`-style placeholders, rendered for
+    /// every runnable instead of the built-in configurations
+    #[arg(long, value_name = "FILE")]
+    template: Option<PathBuf>,
+
+    /// Print the discovered runnables grouped by project and kind, without writing anything
+    #[arg(long, conflicts_with_all = ["dry_run", "stdout", "diff"])]
+    list: bool,
+
+    /// Print a report of the discovered runnables in the given format instead of writing anything
+    #[arg(long, value_enum, value_name = "FORMAT", conflicts_with_all = ["dry_run", "stdout", "diff", "list"])]
+    report: Option<ReportFormat>,
+
+    /// Keep running and regenerate the workspace file whenever a manifest, config file or target directory changes
+    #[arg(long, conflicts_with_all = ["dry_run", "stdout", "diff", "list", "report"])]
+    watch: bool,
+
+    /// List every discovered manifest in rust-analyzer's `linkedProjects` setting
+    #[arg(long)]
+    linked_projects: bool,
+
+    /// Search every directory, including those excluded by .gitignore files
+    #[arg(long)]
+    no_gitignore: bool,
+
+    /// Glob (relative to the root) of directories to skip during discovery, e.g. 'vendor/**' (repeatable)
+    #[arg(long, value_name = "GLOB", value_parser = Glob::new)]
+    exclude: Vec<Glob>,
+
+    /// Glob (relative to the root) a project's directory must match to be processed, e.g. 'services/**' (repeatable)
+    #[arg(long, value_name = "GLOB", value_parser = Glob::new)]
+    include: Vec<Glob>,
+
+    /// How many directory levels below the root to search; 0 only checks the root itself
+    #[arg(long, value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// Descend into symlinked directories (symlink cycles are detected and skipped)
+    #[arg(long)]
+    follow_symlinks: bool,
+
+    /// Name of a directory to skip wherever it appears, in addition to target and node_modules (repeatable)
+    #[arg(long = "ignore-dir", value_name = "NAME")]
+    ignore_dirs: Vec<String>,
+
+    /// Also search directories whose name starts with a dot
+    #[arg(long)]
+    include_hidden: bool,
+
+    /// Feature set `cargo metadata` resolves during discovery; pick another one when enabling
+    /// all features doesn't resolve
+    #[arg(long, value_enum, value_name = "FEATURES", default_value = "all")]
+    discovery_features: DiscoveryFeatures,
+
+    /// Always run cargo metadata instead of reusing results cached for unchanged projects
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Only generate configurations for the packages in the workspace's `default-members`
+    #[arg(long)]
+    default_members_only: bool,
+
+    /// Only generate configurations for this package (repeatable)
+    #[arg(short, long = "package", value_name = "NAME")]
+    packages: Vec<String>,
+
+    /// Only generate configurations for binaries (no examples, tests or benches)
+    #[arg(long, conflicts_with = "examples_only")]
+    bins_only: bool,
+
+    /// Only generate configurations for examples (no binaries, tests or benches)
+    #[arg(long)]
+    examples_only: bool,
+
+    /// Leave out runnables built for a WebAssembly target, which a native debugger can't run
+    #[arg(long)]
+    skip_wasm: bool,
+
+    /// Build and debug the optimized release profile instead of the dev profile
+    #[arg(long, conflicts_with = "profiles")]
+    release: bool,
+
+    /// Profiles to generate configurations for, e.g. 'debug,release' for one configuration per profile
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "dev")]
+    profiles: Vec<Profile>,
+
+    /// Target triple to build for, e.g. 'aarch64-unknown-linux-gnu' (defaults to the host)
+    #[arg(long, value_name = "TRIPLE")]
+    target: Option<String>,
+
+    /// Add a "Run all binaries" compound that starts every binary's launch configuration at once
+    #[arg(long)]
+    compound_all: bool,
+
+    /// Cargo feature to enable in every launch configuration and build task, on top of each
+    /// target's required features (repeatable or comma-separated)
+    #[arg(long, value_name = "NAME", value_delimiter = ',')]
+    features: Vec<String>,
+
+    /// Disable each package's default features in the generated launch configurations and build
+    /// tasks (discovery still sees every target)
+    #[arg(long)]
+    no_default_features: bool,
+
+    /// TOML file mapping runnable names to program arguments, e.g. '"my_pkg::my_bin" = ["--port", "8080"]'
+    #[arg(long, value_name = "FILE")]
+    args_file: Option<PathBuf>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-struct WorkspaceLaunchConfig {
-    version: String,
-    configurations: Vec<Configuration>,
+/// Formats `--report` can print.
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    /// A JSON array with one object per runnable
+    Json,
 }
 
-#[derive(Serialize, Deserialize)]
-struct WorkspaceFile {
-    folders: Vec<WorkspaceFolder>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    settings: Option<serde_json::Value>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    launch: Option<WorkspaceLaunchConfig>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    tasks: Option<serde_json::Value>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    extensions: Option<serde_json::Value>,
+/// One runnable in the `--report json` output. Only the documented fields are written, so the
+/// report keeps its shape when [`Runnable`] changes.
+#[derive(Serialize)]
+struct ReportEntry<'a> {
+    name: &'a str,
+    package: &'a str,
+    /// `binary`, `example`, `test` or `bench`
+    kind: &'static str,
+    /// `lib`, `bin` or `integration` for tests, otherwise null
+    test_target: Option<&'static str>,
+    /// Whether a bench uses the libtest harness, otherwise null
+    harness: Option<bool>,
+    required_features: &'a [String],
+    project_path: &'a Path,
 }
 
-#[derive(Serialize, Deserialize)]
-struct WorkspaceFolder {
-    path: String,
+impl<'a> From<&'a Runnable> for ReportEntry<'a> {
+    fn from(runnable: &'a Runnable) -> Self {
+        let (kind, test_target, harness) = match &runnable.runnable_type {
+            RunnableType::Binary => ("binary", None, None),
+            RunnableType::Example => ("example", None, None),
+            RunnableType::Test(TestTarget::Lib) => ("test", Some("lib"), None),
+            RunnableType::Test(TestTarget::Bin) => ("test", Some("bin"), None),
+            RunnableType::Test(TestTarget::Integration) => ("test", Some("integration"), None),
+            RunnableType::Bench { harness } => ("bench", None, Some(*harness)),
+        };
+        ReportEntry {
+            name: &runnable.name,
+            package: &runnable.package,
+            kind,
+            test_target,
+            harness,
+            required_features: &runnable.required_features,
+            project_path: &runnable.project_path,
+        }
+    }
 }
 
 /// Generates VS Code multi-root workspace configurations with launch configurations for all discovered Rust projects.
@@ -92,457 +314,598 @@ struct WorkspaceFolder {
 ///
 /// # Usage
 ///
-/// rust-vscode-workspace-configurator [--root <ROOT>]
+/// rust-vscode-workspace-configurator [--root <ROOT>... | --manifest-path <FILE>] [--dry-run | --stdout | --diff] [--quiet | --verbose...] [--list | --report json] [--watch] [--strict] [--allow-empty] [--validate] [--no-backup] [--max-backups <N>] [--folder-names <STRATEGY>] [--name <NAME>] [--debugger <DEBUGGER>] [--gdb-path <PATH>] [--config-type <TYPE>] [--env <KEY=VALUE>]... [--backtrace <MODE>] [--rust-log <FILTER>] [--pre-launch-build] [--name-features] [--raw-names] [--program-mode] [--stop-at-entry] [--terminal <TERMINAL>] [--env-file <PATH>] [--init-command <CMD>]... [--source-map <FROM=TO>]... [--with-attach] [--remote-host <HOST> --remote-port <PORT>] [--template <FILE>] [--linked-projects] [--no-gitignore] [--exclude <GLOB>]... [--include <GLOB>]... [--max-depth <N>] [--follow-symlinks] [--ignore-dir <NAME>]... [--include-hidden] [--discovery-features <FEATURES>] [--no-cache] [--default-members-only] [--package <NAME>]... [--bins-only | --examples-only] [--skip-wasm] [--release | --profiles <PROFILES>] [--target <TRIPLE>] [--compound-all] [--features <NAME>]... [--no-default-features] [--args-file <FILE>]
 ///
-/// - `--root`: Root directory to search for Rust projects recursively (defaults to current directory)
+/// - `--root`: Root directory to search for Rust projects recursively (defaults to current directory; repeatable, the workspace file then goes to their common parent)
+/// - `--manifest-path`: Use only the project of this Cargo.toml, writing the workspace file next to it
+/// - `--dry-run`: Print the generated workspace JSON instead of writing it to disk
+/// - `--stdout`: Like `--dry-run`, but keep stdout free of anything but the JSON
+/// - `--diff`: List the folders and launch configurations that would be added (`+`), removed (`-`) or changed (`~`)
+/// - `--quiet`/`-q`: Don't print progress, only warnings and errors
+/// - `--verbose`/`-v`: Print debugging details such as skipped directories (repeat for more)
+/// - `--list`: Print the discovered runnables, with their required features, grouped by project and kind; nothing is written
+/// - `--report json`: Print the discovered runnables as a JSON array instead of writing anything
+/// - `--watch`: Keep running and regenerate the workspace file whenever a manifest, cargo config file, `.rust-vscode.toml` or the set of target files changes
+/// - `--strict`: Exit with an error, writing nothing, when any project's metadata can't be read
+/// - `--allow-empty`: Exit successfully, writing nothing, when no Cargo.toml is found
+/// - `--validate`: Check the folders, launch configurations, tasks and extensions before writing; fail with the problems found
+/// - `--no-backup`: Overwrite an existing workspace file without creating a .backup copy
+/// - `--max-backups`: Number of workspace file backups to keep (defaults to 5)
+/// - `--folder-names`: Label workspace folders with the project's package name (`package`, the default) or leave them unlabelled (`path`)
+/// - `--name`: Display name written to the workspace file; otherwise an existing file keeps its name
+/// - `--debugger`: Debugger backend for the launch configurations (`lldb`/`codelldb`, `gdb`, `cppvsdbg` or `lldb-dap`)
+/// - `--gdb-path`: GDB executable for `gdb` configurations' `miDebuggerPath` (defaults to `gdb`, resolved from PATH)
+/// - `--config-type`: Debug adapter `type` written verbatim into every launch configuration
+/// - `--env`: Environment variable added to every launch configuration (repeatable)
+/// - `--backtrace`: Set `RUST_BACKTRACE` (`0`, `1` or `full`) in every launch configuration unless `--env` or the package sets it
+/// - `--rust-log`: Set `RUST_LOG` to this filter in every launch configuration unless `--env` or the package sets it
+/// - `--pre-launch-build`: Run the package's generated cargo build task before each launch
+/// - `--name-features`: Append the required features of a target to its configuration names
+/// - `--raw-names`: Keep configuration names literally instead of normalizing their whitespace
+/// - `--program-mode`: Point CodeLLDB binary and example configurations at the built artifact (`program`) instead of a `cargo` block
+/// - `--stop-at-entry`: Break at the program entry point (`stopOnEntry`/`stopAtEntry`)
+/// - `--terminal`: CodeLLDB `terminal` for the program's I/O (`integrated`, `external` or `console`)
+/// - `--env-file`: Dotenv file referenced as `envFile` from every launch configuration
+/// - `--init-command`: LLDB command added to CodeLLDB's or lldb-dap's `initCommands` (repeatable)
+/// - `--source-map`: `FROM=TO` source path remapping added to CodeLLDB's or lldb-dap's `sourceMap` (repeatable)
+/// - `--with-attach`: Add an "Attach to" configuration per binary that picks the process to debug
+/// - `--remote-host`/`--remote-port`: Add a CodeLLDB "Remote debug" configuration per binary connecting to a debug server on that address
+/// - `--template`: Render every launch configuration from a JSON template with `{{name}}`, `{{package}}`, `{{bin}}`, `{{kind}}`, `{{cwd}}`, `{{manifest_path}}` and `{{features}}` placeholders
+/// - `--linked-projects`: Set `rust-analyzer.linkedProjects` to the manifests of all discovered projects
+/// - `--no-gitignore`: Also search directories excluded by .gitignore files
+/// - `--exclude`: Glob of directories (relative to the root) to skip during discovery (repeatable)
+/// - `--include`: Glob (relative to the root) that discovered projects must match to be kept (repeatable)
+/// - `--max-depth`: Directory levels below the root to search (0 only checks the root itself)
+/// - `--follow-symlinks`: Descend into symlinked directories, skipping symlink cycles
+/// - `--ignore-dir`: Name of a directory to skip wherever it appears, like `target` and `node_modules` (repeatable)
+/// - `--include-hidden`: Also search dot-prefixed directories (except `.git`)
+/// - `--discovery-features`: Feature set (`all`, `default` or `none`) cargo metadata resolves during discovery
+/// - `--no-cache`: Run cargo metadata for every project instead of reusing the results of earlier runs for unchanged ones
+/// - `--default-members-only`: Only generate configurations for the workspace's `default-members`, as `cargo run` picks from
+/// - `--package`/`-p`: Only generate configurations for the named package (repeatable)
+/// - `--bins-only`: Only generate configurations for binaries
+/// - `--examples-only`: Only generate configurations for examples
+/// - `--skip-wasm`: Leave out runnables whose target (`--target`, `CARGO_BUILD_TARGET` or the cargo config's `build.target`) is WebAssembly
+/// - `--release`: Generate configurations for the release profile
+/// - `--profiles`: Comma-separated profiles (`dev`/`debug`, `release`) to generate configurations for
+/// - `--target`: Target triple passed to cargo; gdb/cppvsdbg programs are taken from `target/<TRIPLE>/<profile>`
+/// - `--compound-all`: Add a "Run all binaries" compound launching every binary configuration
+/// - `--features`: Extra cargo feature enabled in every configuration and build task (repeatable)
+/// - `--no-default-features`: Disable default features in the generated cargo arguments (discovery is unaffected)
+/// - `--args-file`: TOML file mapping runnable names (such as `my_pkg::my_bin`) to program arguments
 ///
 /// # Behavior
 ///
 /// - If the root directory contains a Cargo.toml, processes that project directly
-/// - Otherwise, recursively searches subdirectories for all Rust projects
+/// - Otherwise, recursively searches subdirectories for all Rust projects, skipping .gitignored directories
 /// - Creates a multi-root workspace with separate folders for each discovered project
 /// - Generates namespaced launch configurations to avoid conflicts between projects
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
-    
-    let root_dir = args.root.unwrap_or_else(|| std::env::current_dir().unwrap());
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches)?;
+    init_logging(args.quiet, args.verbose, args.stdout || args.list || args.report.is_some());
+
+    let watched = generate(&matches)?;
+    if args.watch {
+        watch(watched, &matches);
+    }
+    Ok(())
+}
+
+/// Discovers the projects below the roots given on the command line and writes their workspace
+/// file, returning what `--watch` should check for changes.
+fn generate(matches: &ArgMatches) -> Result<WatchList, Box<dyn std::error::Error>> {
+    let mut args = Args::from_arg_matches(matches)?;
+
+    let roots = match &args.manifest_path {
+        // A root containing a manifest is processed directly rather than searched
+        Some(manifest_path) => vec![manifest_directory(manifest_path)?],
+        None if args.roots.is_empty() => vec![std::env::current_dir()?],
+        None => args.roots.clone(),
+    };
+    // Several roots share one workspace file in their closest common directory, named after all of them
+    let (root_dir, label) = match roots.as_slice() {
+        [root] => (root.clone(), None),
+        _ => {
+            let roots = roots.iter().map(std::path::absolute).collect::<Result<Vec<_>, _>>()?;
+            let names: Vec<String> = roots.iter()
+                .map(|root| root.file_name().map_or_else(|| "root".to_string(), |n| n.to_string_lossy().into_owned()))
+                .collect();
+            (multi_root_directory(&roots)?, Some(names.join("+")))
+        },
+    };
     let output_dir = root_dir.clone();
+
+    let config_path = root_dir.join(CONFIG_FILE_NAME);
+    if config_path.is_file() {
+        info!("Using defaults from {}", config_path.display());
+        apply_config_file(&mut args, matches, load_config_file(&config_path)?)?;
+    }
+
+    // A template is rendered once per runnable as is, so settings that shape the built-in
+    // configurations don't reach it; the config file can set some of them, so warn rather than reject
+    if args.template.is_some() {
+        let ignored = [
+            ("--release/--profiles", args.release || args.profiles != [Profile::Dev]),
+            ("--pre-launch-build", args.pre_launch_build),
+            ("--with-attach", args.with_attach),
+            ("--remote-host/--remote-port", args.remote_host.is_some()),
+        ];
+        for (flag, given) in ignored {
+            if given {
+                warn!("{} has no effect on launch configurations rendered from --template", flag);
+            }
+        }
+    }
+
+    let launch_options = LaunchOptions {
+        debugger: args.debugger.unwrap_or_else(detect_default_debugger),
+        config_type: args.config_type,
+        gdb_path: args.gdb_path,
+        env: args.env.into_iter().collect(),
+        default_env: [("RUST_BACKTRACE", args.backtrace), ("RUST_LOG", args.rust_log)]
+            .into_iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value?)))
+            .collect(),
+        pre_launch_build: args.pre_launch_build,
+        name_features: args.name_features,
+        raw_names: args.raw_names,
+        program_mode: args.program_mode,
+        profiles: if args.release {
+            vec![Profile::Release]
+        } else {
+            // Repeating a profile would only produce duplicate configurations
+            let mut profiles = Vec::new();
+            for profile in &args.profiles {
+                if !profiles.contains(profile) {
+                    profiles.push(*profile);
+                }
+            }
+            profiles
+        },
+        target: args.target,
+        compound_all: args.compound_all,
+        features: {
+            let mut features: Vec<String> = Vec::new();
+            for feature in &args.features {
+                if !feature.is_empty() && !features.contains(feature) {
+                    features.push(feature.clone());
+                }
+            }
+            features
+        },
+        no_default_features: args.no_default_features,
+        stop_at_entry: args.stop_at_entry,
+        terminal: args.terminal,
+        env_file: args.env_file.as_deref().map(std::path::absolute).transpose()?,
+        init_commands: args.init_commands,
+        source_map: args.source_map.into_iter().collect(),
+        with_attach: args.with_attach,
+        remote: args.remote_host.zip(args.remote_port).map(|(host, port)| RemoteTarget { host, port }),
+        template: args.template.as_deref().map(load_template).transpose()?,
+        program_args: match &args.args_file {
+            Some(path) => load_program_args(path)?,
+            None => BTreeMap::new(),
+        },
+    };
     
-    println!("Searching for Rust projects in: {}", root_dir.display());
+    for root in &roots {
+        info!("Searching for Rust projects in: {}", root.display());
+    }
     
-    let runnables = discover_runnables(&root_dir)?;
+    let discovery_options = DiscoveryOptions {
+        respect_gitignore: !args.no_gitignore,
+        exclude: build_globset(&args.exclude)?,
+        include: build_globset(&args.include)?,
+        max_depth: args.max_depth,
+        follow_symlinks: args.follow_symlinks,
+        ignore_dirs: args.ignore_dirs,
+        include_hidden: args.include_hidden,
+        features: args.discovery_features,
+        packages: args.packages,
+        default_members_only: args.default_members_only,
+        metadata_cache: if args.no_cache { None } else { metadata_cache_dir() },
+    };
+    info!("Reading cargo metadata with {}", discovery_options.features.description());
+    let Discovery { projects, mut runnables, failed_projects } = match discover_runnables_in_roots(&roots, &discovery_options) {
+        Ok(discovery) => discovery,
+        Err(e @ Error::NoProjectsFound { .. }) if args.allow_empty => {
+            info!("{}; nothing to write", e);
+            return Ok(WatchList::new(&root_dir, &roots, &[], &[]));
+        },
+        // Report library errors by their message rather than their Debug representation
+        Err(e) => return Err(e.to_string().into()),
+    };
+    if args.strict && !failed_projects.is_empty() {
+        return Err(format!("{} of {} projects could not be read (see warnings above)", failed_projects.len(), projects.len()).into());
+    }
+    // Taken before the filters below so that every package stays watched
+    let watched = WatchList::new(&root_dir, &roots, &projects, &runnables);
+    if args.bins_only {
+        runnables.retain(|r| matches!(r.runnable_type, RunnableType::Binary));
+    } else if args.examples_only {
+        runnables.retain(|r| matches!(r.runnable_type, RunnableType::Example));
+    }
+    if args.skip_wasm {
+        // An explicit --target applies to every package, otherwise each builds for its configured target
+        let before = runnables.len();
+        runnables.retain(|r| !launch_options.target.as_deref().or(r.build_target.as_deref()).is_some_and(is_wasm_target));
+        if runnables.len() < before {
+            info!("Skipped {} WebAssembly runnable(s)", before - runnables.len());
+        }
+    }
     
+    if args.list {
+        print_runnables(&projects, &runnables, &root_dir);
+        return Ok(watched);
+    }
+    if let Some(ReportFormat::Json) = args.report {
+        let report: Vec<ReportEntry> = runnables.iter().map(ReportEntry::from).collect();
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(watched);
+    }
+
+    // Discovery fails when there are no projects at all; projects without runnables (such as
+    // library-only crates) still get a workspace so rust-analyzer sees their folders
     if runnables.is_empty() {
-        println!("No runnables found in {}", root_dir.display());
-        return Ok(());
+        info!("No runnables found in {}; writing a workspace without launch configurations", root_dir.display());
+    } else {
+        info!("Found {} runnables:", runnables.len());
+        for runnable in &runnables {
+            info!("  {} ({:?}) in package {}", runnable.name, runnable.runnable_type, runnable.package);
+        }
     }
     
-    println!("Found {} runnables:", runnables.len());
-    for runnable in &runnables {
-        println!("  {} ({:?}) in package {}", runnable.name, runnable.runnable_type, runnable.package);
+    let sections = GeneratedSections {
+        launch: generate_workspace_launch_config(&runnables, &root_dir, &launch_options),
+        tasks: generate_workspace_tasks(&runnables, &root_dir, &launch_options),
+        extensions: generate_extensions(launch_options.debugger),
+        settings: generate_settings(if args.linked_projects { &projects } else { &[] }, &root_dir),
+    };
+    let write_options = WriteOptions {
+        dry_run: args.dry_run || args.stdout,
+        diff: args.diff,
+        backup: !args.no_backup,
+        max_backups: args.max_backups,
+        folder_names: args.folder_names,
+        label,
+        name: args.name,
+        validate: args.validate,
+    };
+    write_workspace_launch_config(&output_dir, &sections, &projects, &root_dir, &write_options)
+        .map_err(|e| e.to_string())?;
+
+    // Individual failures scroll by with the discovery output, so repeat the count at the end
+    if !failed_projects.is_empty() {
+        warn!("Skipped {} of {} projects (see warnings above)", failed_projects.len(), projects.len());
     }
     
-    let launch_config = generate_workspace_launch_config(&runnables, &root_dir);
-    write_workspace_launch_config(&output_dir, &launch_config, &runnables, &root_dir)?;
+    if args.dry_run || args.stdout || args.diff {
+        return Ok(watched);
+    }
     
-    let workspace_filename = generate_workspace_filename(&root_dir);
-    println!("Created {} with launch configurations in {}", workspace_filename, output_dir.display());
+    let workspace_filename = write_options.workspace_filename(&root_dir);
+    info!("Created {} with launch configurations in {}", workspace_filename, output_dir.display());
     
-    Ok(())
+    Ok(watched)
 }
 
-fn discover_runnables(root_dir: &Path) -> Result<Vec<Runnable>, Box<dyn std::error::Error>> {
-    let mut runnables = Vec::new();
-    let mut found_projects = Vec::new();
-
-    // First try to see if the root directory itself is a Rust project
-    let manifest_path = root_dir.join("Cargo.toml");
-    if manifest_path.exists() {
-        found_projects.push(root_dir.to_path_buf());
-    } else {
-        // Search for Rust projects in subdirectories
-        find_rust_projects_recursive(root_dir, &mut found_projects)?;
-        
-        if found_projects.is_empty() {
-            return Err(format!("No Rust projects (Cargo.toml files) found in {}", root_dir.display()).into());
+/// Prints the runnables of every project grouped by kind, for `--list`.
+fn print_runnables(projects: &[PathBuf], runnables: &[Runnable], root_dir: &Path) {
+    let heading = |kind: &RunnableType| match kind {
+        RunnableType::Binary => "Binaries",
+        RunnableType::Example => "Examples",
+        RunnableType::Test(_) => "Tests",
+        RunnableType::Bench { .. } => "Benches",
+    };
+    for project in projects {
+        let relative = pathdiff::diff_paths(project, root_dir)
+            .filter(|path| !path.as_os_str().is_empty())
+            .unwrap_or_else(|| PathBuf::from("."));
+        println!("{}", relative.display());
+
+        let mut project_runnables: Vec<&Runnable> = runnables.iter().filter(|r| &r.project_path == project).collect();
+        if project_runnables.is_empty() {
+            println!("  (no runnables)");
+            continue;
         }
-    }
-
-    println!("Found {} Rust project(s):", found_projects.len());
-    for project_path in &found_projects {
-        println!("  {}", project_path.display());
-    }
-
-    // Process each found project
-    for project_path in found_projects {
-        let manifest_path = project_path.join("Cargo.toml");
-        
-        // Get metadata for the workspace or single package
-        let metadata = match MetadataCommand::new()
-            .manifest_path(&manifest_path)
-            .features(CargoOpt::AllFeatures)
-            .exec() {
-                Ok(metadata) => metadata,
-                Err(e) => {
-                    eprintln!("Warning: Failed to read metadata for {}: {}", manifest_path.display(), e);
-                    continue;
-                }
-            };
-
-        // Canonicalize the project path for consistent comparison
-        let canonical_project_path = project_path.canonicalize().unwrap_or_else(|_| project_path.clone());
-
-        // Handle both workspace and single package cases
-        let packages_to_process: Vec<&cargo_metadata::Package> = if metadata.workspace_members.is_empty() {
-            // Single package project - find the package that matches this manifest path
-            // Try to canonicalize paths to handle different path representations
-            let canonical_manifest = manifest_path.canonicalize().unwrap_or(manifest_path.clone());
-            
-            match metadata.packages.iter().find(|p| {
-                let pkg_manifest_canonical = p.manifest_path.as_std_path().canonicalize()
-                    .unwrap_or_else(|_| p.manifest_path.as_std_path().to_path_buf());
-                pkg_manifest_canonical == canonical_manifest
-            }) {
-                Some(package) => vec![package],
-                None => {
-                    eprintln!("Warning: Could not find package for manifest {}", manifest_path.display());
-                    continue;
+        project_runnables.sort_by(|a, b| a.name.cmp(&b.name));
+        for kind in ["Binaries", "Examples", "Tests", "Benches"] {
+            let of_kind: Vec<&&Runnable> = project_runnables.iter().filter(|r| heading(&r.runnable_type) == kind).collect();
+            if of_kind.is_empty() {
+                continue;
+            }
+            println!("  {}:", kind);
+            for runnable in of_kind {
+                // Feature gates are what most often keeps a target from building
+                match runnable.required_features.as_slice() {
+                    [] => println!("    {}", runnable.name),
+                    features => println!("    {} [features: {}]", runnable.name, features.join(", ")),
                 }
             }
-        } else {
-            // Workspace project - process all workspace members that are in this project directory
-            metadata.packages.iter()
-                .filter(|p| {
-                    // Check if this package's manifest is under the current project path
-                    let pkg_manifest_dir = p.manifest_path.parent().unwrap_or(&p.manifest_path);
-                    let pkg_canonical_dir = pkg_manifest_dir.as_std_path().canonicalize()
-                        .unwrap_or_else(|_| pkg_manifest_dir.as_std_path().to_path_buf());
-                    pkg_canonical_dir.starts_with(&canonical_project_path)
-                })
-                .collect()
-        };
+        }
+    }
+}
 
-        if packages_to_process.is_empty() {
-            eprintln!("Warning: No packages found for project {}", project_path.display());
+/// How often `--watch` looks for changes.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Regenerates the workspace file whenever a file in `watched` changes. Runs until the process is
+/// interrupted.
+fn watch(mut watched: WatchList, matches: &ArgMatches) -> ! {
+    info!("Watching for changes to manifests and config files; press Ctrl+C to stop");
+    let mut snapshot = watched.snapshot();
+    loop {
+        std::thread::sleep(WATCH_INTERVAL);
+        let mut current = watched.snapshot();
+        if current == snapshot {
             continue;
         }
-
-        // Process targets for each package
-        for package in packages_to_process {
-            // Process targets for this package
-            for target in &package.targets {
-                if target.kind.contains(&TargetKind::Bin) {
-                    runnables.push(Runnable {
-                        name: format!("{}::{}", package.name, target.name),
-                        package: package.name.to_string(),
-                        runnable_type: RunnableType::Binary,
-                        required_features: target.required_features.clone(),
-                        project_path: project_path.clone(),
-                    });
-                }
-
-                // Add example targets
-                if target.kind.contains(&TargetKind::Example) {
-                    runnables.push(Runnable {
-                        name: format!("{}::{} (example)", package.name, target.name),
-                        package: package.name.to_string(),
-                        runnable_type: RunnableType::Example,
-                        required_features: target.required_features.clone(),
-                        project_path: project_path.clone(),
-                    });
-                }
+        // Let a burst of changes, such as a branch switch, settle before regenerating once
+        loop {
+            std::thread::sleep(WATCH_INTERVAL);
+            let next = watched.snapshot();
+            if next == current {
+                break;
             }
+            current = next;
         }
-    }
 
-    Ok(runnables)
+        info!("Projects changed; regenerating the workspace");
+        // A broken manifest mid-edit shouldn't end the session; keep watching what was found last
+        match generate(matches) {
+            Ok(regenerated) => watched = regenerated,
+            Err(e) => error!("{}", e),
+        }
+        snapshot = watched.snapshot();
+    }
 }
 
-fn find_rust_projects_recursive(dir: &Path, projects: &mut Vec<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
-    if !dir.is_dir() {
-        return Ok(());
-    }
+/// What `--watch` checks between regenerations, worked out from the last discovery so that a poll
+/// only looks at a fixed set of paths instead of searching the tree again.
+struct WatchList {
+    /// Manifests, cargo config files, `.rust-vscode.toml` and the directories cargo discovers
+    /// targets in, whose modification time changes when a target file is added or removed
+    paths: Vec<PathBuf>,
+    /// The roots and project directories, whose subdirectories are listed so a project created
+    /// directly inside one is noticed. Their own modification times also change when the workspace
+    /// file or `Cargo.lock` is rewritten, so they can't be watched like `paths`.
+    listed_dirs: Vec<PathBuf>,
+}
 
-    // Check if this directory contains a Cargo.toml
-    let cargo_toml = dir.join("Cargo.toml");
-    if cargo_toml.exists() {
-        projects.push(dir.to_path_buf());
-        // Don't recurse into subdirectories of a Rust project to avoid nested projects
-        return Ok(());
+impl WatchList {
+    fn new(root_dir: &Path, roots: &[PathBuf], projects: &[PathBuf], runnables: &[Runnable]) -> Self {
+        let mut paths = vec![root_dir.join(CONFIG_FILE_NAME)];
+        let mut listed_dirs: Vec<PathBuf> = roots.iter().chain(projects).cloned().collect();
+        for dir in &listed_dirs {
+            paths.push(dir.join(".cargo").join("config"));
+            paths.push(dir.join(".cargo").join("config.toml"));
+        }
+        // Workspace members have manifests and target directories of their own
+        let package_dirs = projects.iter().chain(runnables.iter().map(|runnable| &runnable.package_path));
+        for dir in package_dirs {
+            paths.push(dir.join("Cargo.toml"));
+            for target_dir in [&["src"][..], &["src", "bin"], &["examples"], &["tests"], &["benches"]] {
+                paths.push(target_dir.iter().fold(dir.clone(), |path, name| path.join(name)));
+            }
+        }
+        paths.sort();
+        paths.dedup();
+        listed_dirs.sort();
+        listed_dirs.dedup();
+        WatchList { paths, listed_dirs }
     }
 
-    // Recursively search subdirectories
-    let entries = match std::fs::read_dir(dir) {
-        Ok(entries) => entries,
-        Err(_) => return Ok(()), // Skip directories we can't read
-    };
-
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
-        
-        if path.is_dir() {
-            // Skip common directories that are unlikely to contain Rust projects
-            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                if name.starts_with('.') || name == "target" || name == "node_modules" {
-                    continue;
+    /// Modification times of the watched paths, `None` for those that don't exist, along with the
+    /// subdirectories of the listed directories.
+    fn snapshot(&self) -> BTreeMap<PathBuf, Option<SystemTime>> {
+        let mut files: BTreeMap<PathBuf, Option<SystemTime>> = self.paths.iter()
+            .map(|path| (path.clone(), fs::metadata(path).and_then(|m| m.modified()).ok()))
+            .collect();
+        for dir in &self.listed_dirs {
+            for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+                if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    files.entry(entry.path()).or_insert(None);
                 }
             }
-            
-            find_rust_projects_recursive(&path, projects)?;
         }
+        files
     }
+}
 
-    Ok(())
+/// The user's own cache directory for cargo metadata, following each platform's convention:
+/// `%LOCALAPPDATA%` on Windows, `~/Library/Caches` on macOS and `$XDG_CACHE_HOME` (or `~/.cache`)
+/// elsewhere. Never a shared location such as `/tmp`, where another user could plant entries.
+fn metadata_cache_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::home_dir().map(|home| home.join("Library").join("Caches"))
+    } else {
+        std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| std::env::home_dir().map(|home| home.join(".cache")))
+    };
+    Some(base?.join("rust-vscode-workspace-configurator"))
 }
 
-fn generate_workspace_name(root_dir: &Path, project_paths: &[PathBuf]) -> String {
-    // If only one project, use its name
-    if project_paths.len() == 1 {
-        if let Some(project_name) = project_paths[0].file_name().and_then(|n| n.to_str()) {
-            return format!("{} (Rust)", project_name);
-        }
+/// Team-wide defaults committed next to the projects.
+const CONFIG_FILE_NAME: &str = ".rust-vscode.toml";
+
+/// Defaults read from `.rust-vscode.toml` in the root directory. Keys are named after the
+/// command-line flags they stand in for.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct ConfigFile {
+    debugger: Option<String>,
+    gdb_path: Option<String>,
+    env: BTreeMap<String, String>,
+    pre_launch_build: bool,
+    exclude: Vec<String>,
+    include: Vec<String>,
+    max_depth: Option<usize>,
+    discovery_features: Option<String>,
+    profiles: Option<Vec<String>>,
+    target: Option<String>,
+    features: Vec<String>,
+    no_default_features: bool,
+}
+
+fn load_config_file(path: &Path) -> Result<ConfigFile, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    toml::from_str(&content).map_err(|e| format!("Invalid {}: {}", path.display(), e))
+}
+
+/// Fills in the settings the command line left out from the config file. Flags given on the
+/// command line win; repeatable flags and `--env` add to the file's values.
+fn apply_config_file(args: &mut Args, matches: &ArgMatches, config: ConfigFile) -> Result<(), String> {
+    let from_command_line = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    if args.debugger.is_none()
+        && let Some(debugger) = &config.debugger {
+        args.debugger = Some(parse_enum("debugger", debugger)?);
     }
-    
-    // For multiple projects, use the root directory name with project count
-    let root_name = root_dir
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("Rust Projects");
-    
-    if project_paths.len() > 1 {
-        format!("{} ({} Rust Projects)", root_name, project_paths.len())
-    } else {
-        format!("{} (Rust)", root_name)
+    args.gdb_path = args.gdb_path.take().or(config.gdb_path);
+    // Later entries win when the environment is collected, so the command line goes last
+    args.env.splice(0..0, config.env);
+    args.pre_launch_build |= config.pre_launch_build;
+    let globs = |patterns: &[String]| {
+        patterns.iter()
+            .map(|pattern| Glob::new(pattern).map_err(|e| format!("Invalid glob '{}' in {}: {}", pattern, CONFIG_FILE_NAME, e)))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    args.exclude.splice(0..0, globs(&config.exclude)?);
+    args.include.splice(0..0, globs(&config.include)?);
+    args.max_depth = args.max_depth.or(config.max_depth);
+    if !from_command_line("discovery_features")
+        && let Some(features) = &config.discovery_features {
+        args.discovery_features = parse_enum("discovery-features", features)?;
+    }
+    if !from_command_line("profiles") && !args.release
+        && let Some(profiles) = &config.profiles {
+        args.profiles = profiles.iter().map(|profile| parse_enum("profile", profile)).collect::<Result<_, _>>()?;
     }
+    args.target = args.target.take().or(config.target);
+    args.features.splice(0..0, config.features);
+    args.no_default_features |= config.no_default_features;
+    Ok(())
 }
 
-fn generate_launch_config(runnables: &[Runnable], root_dir: &Path) -> LaunchConfig {
-    let mut configurations = Vec::new();
-    
-    for runnable in runnables {
-        // Calculate relative path from root to project
-        let relative_path = match pathdiff::diff_paths(&runnable.project_path, root_dir) {
-            Some(path) => path,
-            None => runnable.project_path.clone(),
-        };
-        
-        let cwd = if relative_path == Path::new("") || relative_path == Path::new(".") {
-            "${workspaceFolder}".to_string()
-        } else {
-            format!("${{workspaceFolder}}/{}", relative_path.display())
-        };
-        
-        // Generate manifest path argument for cargo
-        let manifest_path_arg = if relative_path == Path::new("") || relative_path == Path::new(".") {
-            format!("--manifest-path=${{workspaceFolder}}/Cargo.toml")
-        } else {
-            format!("--manifest-path=${{workspaceFolder}}/{}/Cargo.toml", relative_path.display())
-        };
-        
-        let config = match runnable.runnable_type {
-            RunnableType::Binary => {
-                // Extract the actual binary name from the prefixed name
-                let binary_name = runnable.name.split("::").last().unwrap_or(&runnable.name);
-                Configuration {
-                    name: format!("Debug binary '{}'", runnable.name),
-                    config_type: "lldb".to_string(),
-                    request: "launch".to_string(),
-                    cwd: cwd.clone(),
-                    env: EnvVars {
-                        bevy_asset_root: cwd.clone(),
-                    },
-                    cargo: CargoConfig {
-                        args: {
-                            let mut args = if binary_name == "main" || binary_name == runnable.package {
-                                vec!["run".to_string(), format!("--package={}", runnable.package)]
-                            } else {
-                                vec![
-                                    "run".to_string(),
-                                    format!("--bin={}", binary_name),
-                                    format!("--package={}", runnable.package),
-                                ]
-                            };
-
-                            if !runnable.required_features.is_empty() {
-                                let feats = runnable.required_features.join(",");
-                                args.push(format!("--features={}", feats));
-                            }
-
-                            // Add manifest path to ensure proper workspace context
-                            args.push(manifest_path_arg.clone());
-
-                            args
-                        },
-                    },
-                    args: vec![],
-                }
-            },
-            RunnableType::Example => {
-                // Extract the actual example name from the prefixed name
-                let example_name = runnable.name.split("::").nth(1)
-                    .and_then(|s| s.strip_suffix(" (example)"))
-                    .unwrap_or(&runnable.name);
-                Configuration {
-                    name: format!("Debug example '{}'", runnable.name),
-                    config_type: "lldb".to_string(),
-                    request: "launch".to_string(),
-                    cwd: cwd.clone(),
-                    env: EnvVars {
-                        bevy_asset_root: cwd.clone(),
-                    },
-                    cargo: CargoConfig {
-                        args: {
-                            let mut args = vec![
-                                "run".to_string(),
-                                format!("--example={}", example_name),
-                                format!("--package={}", runnable.package),
-                            ];
-
-                            if !runnable.required_features.is_empty() {
-                                let feats = runnable.required_features.join(",");
-                                args.push(format!("--features={}", feats));
-                            }
-
-                            // Add manifest path to ensure proper workspace context
-                            args.push(manifest_path_arg);
-
-                            args
-                        },
-                    },
-                    args: vec![],
-                }
-            },
-        };
-        
-        configurations.push(config);
+fn parse_enum<T: ValueEnum>(key: &str, value: &str) -> Result<T, String> {
+    T::from_str(value, true).map_err(|_| format!("Invalid {} '{}' in {}", key, value, CONFIG_FILE_NAME))
+}
+
+/// Checks that `manifest_path` names an existing Cargo.toml and returns its directory.
+fn manifest_directory(manifest_path: &Path) -> Result<PathBuf, String> {
+    if manifest_path.file_name().is_none_or(|name| name != "Cargo.toml") {
+        return Err(format!("--manifest-path must point to a Cargo.toml file, got {}", manifest_path.display()));
     }
-    
-    LaunchConfig {
-        version: "0.2.0".to_string(),
-        configurations,
+    if !manifest_path.is_file() {
+        return Err(format!("Manifest {} does not exist", manifest_path.display()));
     }
+    Ok(match manifest_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    })
 }
 
-fn generate_workspace_launch_config(runnables: &[Runnable], root_dir: &Path) -> WorkspaceLaunchConfig {
-    let configurations = generate_launch_config(runnables, root_dir).configurations;
-    
-    WorkspaceLaunchConfig {
-        version: "0.2.0".to_string(),
-        configurations,
+/// The directory the workspace file for several roots goes in: their deepest common directory,
+/// or the current directory when they share nothing below the filesystem root (or, on Windows, are
+/// on different drives) and the file would otherwise land at the top of the file system.
+fn multi_root_directory(roots: &[PathBuf]) -> Result<PathBuf, String> {
+    let ancestor = common_ancestor(roots);
+    if ancestor.parent().is_some() {
+        return Ok(ancestor);
+    }
+    let current_dir = std::env::current_dir().map_err(|e| format!("Can't read the current directory: {}", e))?;
+    if current_dir.parent().is_none() {
+        return Err("The roots share no directory below the filesystem root; run the tool from the directory the workspace file should go in".to_string());
     }
+    info!("The roots share no directory below the filesystem root, so the workspace file goes in {}", current_dir.display());
+    Ok(current_dir)
 }
 
-fn generate_workspace_filename(root_dir: &Path) -> String {
-    let root_name = root_dir
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("rust-projects");
-    
-    format!("{}.code-workspace", root_name)
+/// The deepest directory containing every one of `paths`, which must be absolute.
+fn common_ancestor(paths: &[PathBuf]) -> PathBuf {
+    let mut ancestor = paths[0].clone();
+    while !paths.iter().all(|path| path.starts_with(&ancestor)) && ancestor.pop() {}
+    ancestor
 }
 
-fn write_workspace_launch_config(output_dir: &Path, launch_config: &WorkspaceLaunchConfig, runnables: &[Runnable], root_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    let workspace_filename = generate_workspace_filename(root_dir);
-    let workspace_path = output_dir.join(&workspace_filename);
-    
-    let mut workspace_file = if workspace_path.exists() {
-        // Create backup of existing workspace file
-        let base_backup_name = format!("{}.backup", workspace_filename);
-        let mut backup_path = output_dir.join(&base_backup_name);
-        
-        if backup_path.exists() {
-            let mut counter = 1;
-            loop {
-                backup_path = output_dir.join(format!("{}.{}", base_backup_name, counter));
-                if !backup_path.exists() {
-                    break;
-                }
-                counter += 1;
-            }
+/// Prints progress to stdout and problems to stderr, in the format the tool has always used.
+struct ConsoleLogger {
+    /// Send progress to stderr as well, leaving stdout to the workspace JSON
+    progress_to_stderr: bool,
+}
+
+impl log::Log for ConsoleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        // Dependencies such as `ignore` log a lot at debug level; only show our own details
+        metadata.level() <= log::max_level()
+            && (metadata.level() <= Level::Info || metadata.target().starts_with(env!("CARGO_CRATE_NAME")))
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
         }
-        
-        fs::copy(&workspace_path, &backup_path)?;
-        println!("Backed up existing workspace file to {}", backup_path.display());
-        
-        // Read existing workspace file
-        let content = fs::read_to_string(&workspace_path)?;
-        
-        // Try to parse the JSON, with a fallback to clean up common issues
-        let workspace = match serde_json::from_str(&content) {
-            Ok(workspace) => workspace,
-            Err(parse_err) => {
-                // Try to fix common JSON issues like trailing commas
-                eprintln!("Warning: Failed to parse existing workspace file: {}", parse_err);
-                
-                // Use regex to remove trailing commas more reliably
-                let trailing_comma_re = Regex::new(r",(\s*[}\]])").unwrap();
-                let cleaned = trailing_comma_re.replace_all(&content, "$1").to_string();
-                
-                match serde_json::from_str(&cleaned) {
-                    Ok(workspace) => {
-                        eprintln!("Successfully recovered by removing trailing commas");
-                        workspace
-                    },
-                    Err(e) => {
-                        eprintln!("Warning: Failed to parse existing workspace file even after cleanup: {}", e);
-                        eprintln!("Creating a new workspace file instead.");
-                        // Create new workspace file with basic structure
-                        WorkspaceFile {
-                            folders: vec![],
-                            name: None,
-                            settings: None,
-                            launch: None,
-                            tasks: None,
-                            extensions: None,
-                        }
-                    }
-                }
-            }
-        };
-        workspace
-    } else {
-        // Create new workspace file with basic structure
-        WorkspaceFile {
-            folders: vec![],
-            name: None,
-            settings: None,
-            launch: None,
-            tasks: None,
-            extensions: None,
+        match record.level() {
+            Level::Error => eprintln!("Error: {}", record.args()),
+            Level::Warn => eprintln!("Warning: {}", record.args()),
+            Level::Info if self.progress_to_stderr => eprintln!("{}", record.args()),
+            Level::Info => println!("{}", record.args()),
+            Level::Debug | Level::Trace => eprintln!("[{}] {}", record.level().as_str().to_lowercase(), record.args()),
         }
-    };
-    
-    // Collect unique project paths
-    let mut project_paths: Vec<PathBuf> = runnables.iter()
-        .map(|r| r.project_path.clone())
-        .collect();
-    project_paths.sort();
-    project_paths.dedup();
-    
-    // Generate workspace name
-    let workspace_name = generate_workspace_name(root_dir, &project_paths);
-    workspace_file.name = Some(workspace_name);
-    
-    // Create folders for all discovered projects
-    let mut folders = Vec::new();
-    for project_path in &project_paths {
-        let relative_path = match pathdiff::diff_paths(&project_path, root_dir) {
-            Some(path) if path != Path::new("") && path != Path::new(".") => format!("./{}", path.display()),
-            _ => ".".to_string(),
-        };
-        
-        folders.push(WorkspaceFolder {
-            path: relative_path,
-        });
-    }
-    
-    // If no projects found or only root project, add current directory
-    if folders.is_empty() {
-        folders.push(WorkspaceFolder {
-            path: ".".to_string(),
-        });
     }
-    
-    workspace_file.folders = folders;
-    
-    // Clean up null/empty fields to follow VS Code conventions
-    if workspace_file.settings.as_ref().map_or(false, |s| s.is_null()) {
-        workspace_file.settings = None;
+
+    fn flush(&self) {}
+}
+
+/// Installs the console logger: warnings only with --quiet, more detail with each --verbose.
+fn init_logging(quiet: bool, verbose: u8, progress_to_stderr: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::Warn,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    let logger = Box::leak(Box::new(ConsoleLogger { progress_to_stderr }));
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(level);
     }
-    if workspace_file.tasks.as_ref().map_or(false, |t| t.is_null()) {
-        workspace_file.tasks = None;
+}
+
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", s)),
     }
-    if workspace_file.extensions.as_ref().map_or(false, |e| e.is_null() || (e.is_object() && e.as_object().unwrap().is_empty())) {
-        workspace_file.extensions = None;
+}
+
+fn parse_config_type(s: &str) -> Result<String, String> {
+    let config_type = s.trim();
+    if config_type.is_empty() {
+        return Err("the configuration type must not be empty".to_string());
     }
-    
-    // Update the launch section
-    workspace_file.launch = Some((*launch_config).clone());
-    
-    // Write back to file
-    let json_content = serde_json::to_string_pretty(&workspace_file)?;
-    fs::write(workspace_path, json_content)?;
-    
-    Ok(())
+    Ok(config_type.to_string())
+}
+
+/// Reads and checks a `--template` file.
+fn load_template(path: &Path) -> Result<ConfigurationTemplate, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read template {}: {}", path.display(), e))?;
+    let template = ConfigurationTemplate::parse(&content)
+        .map_err(|e| format!("{} ({})", e, path.display()))?;
+    Ok(template)
+}
+
+/// Reads an `--args-file`: a TOML table whose keys are runnable names (as printed in the
+/// discovery summary) and whose values are argument lists.
+fn load_program_args(path: &Path) -> Result<BTreeMap<String, Vec<String>>, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read args file {}: {}", path.display(), e))?;
+    let program_args = toml::from_str(&content)
+        .map_err(|e| format!("Invalid args file {}: {}", path.display(), e))?;
+    Ok(program_args)
 }
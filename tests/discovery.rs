@@ -0,0 +1,1345 @@
+use rust_vscode_workspace_configurator::{
+    build_globset, find_projects, generate_launch_config, Debugger, DiscoveryFeatures, DiscoveryOptions, LaunchOptions,
+    Profile, Runnable, RunnableType, TestTarget, VscodeOverrides,
+};
+use globset::Glob;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Copies a fixture from `tests/fixtures` into a fresh scratch directory so cargo can write
+/// lock files without touching the source tree. Every call gets its own directory so tests
+/// running in parallel never share a copy.
+fn copy_fixture(name: &str) -> PathBuf {
+    static COPIES: AtomicUsize = AtomicUsize::new(0);
+    let copy = COPIES.fetch_add(1, Ordering::SeqCst);
+    let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name);
+    let destination = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fixtures").join(format!("{}-{}", copy, name)).join(name);
+    if destination.exists() {
+        fs::remove_dir_all(&destination).unwrap();
+    }
+    copy_dir(&source, &destination);
+    destination
+}
+
+/// Creates an empty scratch directory for tests that lay out several projects themselves,
+/// clearing whatever an earlier run left there. Each test uses its own name.
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fixtures").join(name);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn copy_dir(source: &Path, destination: &Path) {
+    fs::create_dir_all(destination).unwrap();
+    for entry in fs::read_dir(source).unwrap() {
+        let entry = entry.unwrap();
+        let target = destination.join(entry.file_name());
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir(&entry.path(), &target);
+        } else {
+            fs::copy(entry.path(), target).unwrap();
+        }
+    }
+}
+
+/// Runs the tool with `--stdout` and returns the workspace JSON it would write.
+fn generate_workspace(root: &Path, extra_args: &[&str]) -> serde_json::Value {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+        .arg("--root")
+        .arg(root)
+        .arg("--stdout")
+        .args(extra_args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    // Progress goes to stderr, so stdout is nothing but the JSON
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+fn configuration_names(workspace: &serde_json::Value) -> Vec<String> {
+    workspace["launch"]["configurations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn standalone_crate_ignores_path_dependencies_below_it() {
+    let root = copy_fixture("standalone");
+    let workspace = generate_workspace(&root, &[]);
+    let names = configuration_names(&workspace);
+
+    assert!(names.contains(&"Debug binary 'standalone::standalone'".to_string()), "{:?}", names);
+    assert!(names.iter().all(|name| !name.contains("helper")), "{:?}", names);
+}
+
+fn configuration<'a>(workspace: &'a serde_json::Value, name: &str) -> &'a serde_json::Value {
+    workspace["launch"]["configurations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == name)
+        .unwrap_or_else(|| panic!("no configuration named {}", name))
+}
+
+#[test]
+fn nested_workspace_member_runs_from_its_own_directory() {
+    let root = copy_fixture("workspace");
+    let workspace = generate_workspace(&root, &[]);
+
+    let member = configuration(&workspace, "Debug binary 'game::game'");
+    assert_eq!(member["cwd"], "${workspaceFolder}/crates/game");
+
+    let root_package = configuration(&workspace, "Debug binary 'launcher::launcher'");
+    assert_eq!(root_package["cwd"], "${workspaceFolder}");
+}
+
+#[test]
+fn rewriting_workspace_file_keeps_comments() {
+    let root = copy_fixture("standalone");
+    let workspace_path = root.join("standalone.code-workspace");
+    fs::write(
+        &workspace_path,
+        "{\n  // folders are regenerated\n  \"folders\": [],\n  \"settings\": {\n    // keep formatting on save for the whole team\n    \"editor.formatOnSave\": true, /* agreed in review */\n  },\n}\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+        .arg("--root")
+        .arg(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let content = fs::read_to_string(&workspace_path).unwrap();
+    assert!(content.contains("// keep formatting on save for the whole team"), "{}", content);
+    assert!(content.contains("/* agreed in review */"), "{}", content);
+    assert!(content.contains("\"Debug binary 'standalone::standalone'\""), "{}", content);
+}
+
+#[test]
+fn regenerating_replaces_only_configurations_the_tool_wrote() {
+    let root = copy_fixture("standalone");
+    let workspace_path = root.join("standalone.code-workspace");
+    let existing = serde_json::json!({
+        "folders": [{ "path": "." }],
+        "launch": {
+            "version": "0.2.0",
+            "configurations": [
+                { "name": "Attach to server on staging", "type": "lldb", "request": "attach", "pid": "4242" },
+                { "name": "Debug binary 'standalone::gone'", "type": "lldb", "request": "launch", "generatedBy": "rust-vscode-workspace-configurator" },
+            ],
+        },
+    });
+    fs::write(&workspace_path, serde_json::to_string_pretty(&existing).unwrap()).unwrap();
+
+    let workspace = generate_workspace(&root, &[]);
+    assert_eq!(configuration_names(&workspace), [
+        "Attach to server on staging",
+        "Debug binary 'standalone::standalone'",
+        "Debug unit tests 'standalone::standalone (bin)'",
+    ]);
+    assert!(configuration(&workspace, "Attach to server on staging").get("generatedBy").is_none());
+    assert_eq!(configuration(&workspace, "Debug binary 'standalone::standalone'")["generatedBy"], "rust-vscode-workspace-configurator");
+}
+
+#[test]
+fn hand_written_remote_configurations_survive_remote_generation() {
+    let root = copy_fixture("standalone");
+    let existing = serde_json::json!({
+        "folders": [{ "path": "." }],
+        "launch": {
+            "version": "0.2.0",
+            "configurations": [{ "name": "Remote debug staging board", "type": "lldb", "request": "attach" }],
+        },
+    });
+    fs::write(root.join("standalone.code-workspace"), serde_json::to_string_pretty(&existing).unwrap()).unwrap();
+
+    let workspace = generate_workspace(&root, &["--remote-host", "10.0.0.2", "--remote-port", "1234"]);
+    let names = configuration_names(&workspace);
+    assert!(names.contains(&"Remote debug staging board".to_string()), "{:?}", names);
+    assert!(names.contains(&"Remote debug 'standalone::standalone'".to_string()), "{:?}", names);
+
+    // Without --remote-host the generated remote configuration goes, the user's stays
+    fs::write(root.join("standalone.code-workspace"), serde_json::to_string_pretty(&workspace).unwrap()).unwrap();
+    let names = configuration_names(&generate_workspace(&root, &[]));
+    assert!(names.contains(&"Remote debug staging board".to_string()), "{:?}", names);
+    assert!(!names.contains(&"Remote debug 'standalone::standalone'".to_string()), "{:?}", names);
+}
+
+#[test]
+fn binary_named_after_package_is_selected_explicitly_when_package_has_several() {
+    let root = copy_fixture("multibin");
+    let workspace = generate_workspace(&root, &[]);
+
+    let cargo_args = &configuration(&workspace, "Debug binary 'multibin::multibin'")["cargo"]["args"];
+    assert!(cargo_args.as_array().unwrap().contains(&"--bin=multibin".into()), "{}", cargo_args);
+
+    let cargo_args = &configuration(&workspace, "Debug binary 'multibin::tool'")["cargo"]["args"];
+    assert!(cargo_args.as_array().unwrap().contains(&"--bin=tool".into()), "{}", cargo_args);
+}
+
+#[test]
+fn single_binary_package_runs_without_bin_selection() {
+    let root = copy_fixture("standalone");
+    let workspace = generate_workspace(&root, &[]);
+
+    let cargo_args = &configuration(&workspace, "Debug binary 'standalone::standalone'")["cargo"]["args"];
+    assert!(cargo_args.as_array().unwrap().iter().all(|arg| !arg.as_str().unwrap().starts_with("--bin")), "{}", cargo_args);
+}
+
+#[test]
+fn default_run_binary_uses_plain_cargo_run() {
+    let root = copy_fixture("defaultrun");
+    let workspace = generate_workspace(&root, &[]);
+
+    let cargo_args = &configuration(&workspace, "Debug binary 'defaultrun::server'")["cargo"]["args"];
+    assert!(cargo_args.as_array().unwrap().iter().all(|arg| !arg.as_str().unwrap().starts_with("--bin")), "{}", cargo_args);
+
+    let cargo_args = &configuration(&workspace, "Debug binary 'defaultrun::client'")["cargo"]["args"];
+    assert!(cargo_args.as_array().unwrap().contains(&"--bin=client".into()), "{}", cargo_args);
+}
+
+#[test]
+fn project_without_runnables_still_gets_a_workspace() {
+    let root = copy_fixture("library");
+    let workspace = generate_workspace(&root, &[]);
+
+    assert!(configuration_names(&workspace).is_empty());
+    assert_eq!(workspace["folders"], serde_json::json!([{ "path": ".", "name": "library" }]));
+}
+
+#[test]
+fn projects_without_runnables_get_folders_next_to_other_projects() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let root = scratch_dir("mixed-projects");
+    copy_dir(&fixtures.join("library"), &root.join("library"));
+    copy_dir(&fixtures.join("multibin"), &root.join("multibin"));
+
+    let workspace = generate_workspace(&root, &[]);
+
+    assert_eq!(workspace["folders"], serde_json::json!([{ "path": "./library", "name": "library" }, { "path": "./multibin", "name": "multibin" }]));
+}
+
+#[test]
+fn rewriting_workspace_file_keeps_folder_order_and_names() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let root = scratch_dir("ordered-folders");
+    copy_dir(&fixtures.join("library"), &root.join("library"));
+    copy_dir(&fixtures.join("multibin"), &root.join("multibin"));
+    copy_dir(&fixtures.join("defaultrun"), &root.join("defaultrun"));
+    // A folder added by hand that holds no Rust project
+    fs::create_dir_all(root.join("docs")).unwrap();
+    fs::write(
+        root.join("ordered-folders.code-workspace"),
+        r#"{ "folders": [{ "path": "./multibin", "name": "Tools" }, { "path": "gone" }, { "path": "docs" }, { "path": "library/" }] }"#,
+    )
+    .unwrap();
+
+    let workspace = generate_workspace(&root, &[]);
+
+    assert_eq!(
+        workspace["folders"],
+        serde_json::json!([
+            { "path": "./multibin", "name": "Tools" },
+            { "path": "docs" },
+            { "path": "library/" },
+            { "path": "./defaultrun", "name": "defaultrun" }
+        ])
+    );
+}
+
+#[test]
+fn folder_names_can_be_left_to_vs_code() {
+    let root = copy_fixture("library");
+    let workspace = generate_workspace(&root, &["--folder-names", "path"]);
+
+    assert_eq!(workspace["folders"], serde_json::json!([{ "path": "." }]));
+}
+
+#[test]
+fn binaries_with_explicit_paths_are_selected_by_target_name() {
+    let root = copy_fixture("explicitbin");
+    let workspace = generate_workspace(&root, &[]);
+
+    let cargo_args = &configuration(&workspace, "Debug binary 'explicitbin::explicitbin'")["cargo"]["args"];
+    assert!(cargo_args.as_array().unwrap().contains(&"--bin=explicitbin".into()), "{}", cargo_args);
+
+    let cargo_args = &configuration(&workspace, "Debug binary 'explicitbin::migrate'")["cargo"]["args"];
+    assert!(cargo_args.as_array().unwrap().contains(&"--bin=migrate".into()), "{}", cargo_args);
+
+    // The artifact is named after the target, not the source file
+    let workspace = generate_workspace(&root, &["--debugger", "gdb"]);
+    let program = configuration(&workspace, "Debug binary 'explicitbin::migrate'")["program"].as_str().unwrap();
+    assert!(program.ends_with("/target/debug/migrate"), "{}", program);
+}
+
+#[test]
+fn targets_with_the_same_name_get_unique_configurations() {
+    let root = copy_fixture("collisions");
+    let workspace = generate_workspace(&root, &[]);
+
+    for package in ["alpha", "beta"] {
+        let cargo_args = &configuration(&workspace, &format!("Debug example '{}::hello (example)'", package))["cargo"]["args"];
+        assert!(cargo_args.as_array().unwrap().contains(&format!("--package={}", package).into()), "{}", cargo_args);
+    }
+    let names = configuration_names(&workspace);
+    let unique: std::collections::HashSet<&String> = names.iter().collect();
+    assert_eq!(unique.len(), names.len(), "{:?}", names);
+}
+
+#[test]
+fn packages_with_the_same_name_in_separate_projects_are_told_apart() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let root = scratch_dir("duplicate-projects");
+    copy_dir(&fixtures.join("standalone"), &root.join("first"));
+    copy_dir(&fixtures.join("standalone"), &root.join("second"));
+
+    let workspace = generate_workspace(&root, &[]);
+
+    let names = configuration_names(&workspace);
+    assert!(names.contains(&"Debug binary 'standalone::standalone' [./first]".to_string()), "{:?}", names);
+    assert!(names.contains(&"Debug binary 'standalone::standalone' [./second]".to_string()), "{:?}", names);
+    let unique: std::collections::HashSet<&String> = names.iter().collect();
+    assert_eq!(unique.len(), names.len(), "{:?}", names);
+}
+
+#[test]
+fn diff_lists_changes_without_writing() {
+    let root = copy_fixture("multibin");
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+            .arg("--root")
+            .arg(&root)
+            .arg("--quiet")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    };
+    run(&[]);
+    let workspace_path = root.join("multibin.code-workspace");
+    let written = fs::read_to_string(&workspace_path).unwrap();
+
+    assert_eq!(run(&["--diff"]), "No changes to folders or launch configurations\n");
+
+    let diff = run(&["--diff", "--bins-only"]);
+    assert!(diff.contains("- configuration Debug unit tests 'multibin::tool (bin)'\n"), "{}", diff);
+    assert!(!diff.contains("Debug binary"), "{}", diff);
+    assert_eq!(fs::read_to_string(&workspace_path).unwrap(), written);
+}
+
+#[test]
+fn several_roots_share_one_workspace() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let parent = scratch_dir("several-roots");
+    copy_dir(&fixtures.join("multibin"), &parent.join("tools").join("multibin"));
+    copy_dir(&fixtures.join("defaultrun"), &parent.join("services").join("defaultrun"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+        .arg("--root")
+        .arg(parent.join("tools"))
+        .arg("--root")
+        .arg(parent.join("services"))
+        // Listed twice, but only generated once
+        .arg("--root")
+        .arg(parent.join("tools").join("multibin"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let content = fs::read_to_string(parent.join("tools+services+multibin.code-workspace")).unwrap();
+    let workspace: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(
+        workspace["folders"],
+        serde_json::json!([
+            { "path": "./services/defaultrun", "name": "defaultrun" },
+            { "path": "./tools/multibin", "name": "multibin" }
+        ])
+    );
+    let names = configuration_names(&workspace);
+    assert_eq!(names.iter().filter(|name| name.as_str() == "Debug binary 'multibin::tool'").count(), 1, "{:?}", names);
+    assert!(names.contains(&"Debug binary 'defaultrun::server'".to_string()), "{:?}", names);
+}
+
+#[cfg(unix)]
+#[test]
+fn roots_sharing_only_the_filesystem_root_write_to_the_current_directory() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let parent = scratch_dir("unrelated-roots");
+    copy_dir(&fixtures.join("multibin"), &parent.join("tools").join("multibin"));
+    copy_dir(&fixtures.join("defaultrun"), &parent.join("services").join("defaultrun"));
+    // `/..` is `/` again, but spelled this way the two roots have no directory in common below it
+    let detour = Path::new("/..").join(parent.strip_prefix("/").unwrap());
+    let cwd = parent.join("cwd");
+    fs::create_dir_all(&cwd).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+        .arg("--root")
+        .arg(parent.join("tools"))
+        .arg("--root")
+        .arg(detour.join("services"))
+        .current_dir(&cwd)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(cwd.join("tools+services.code-workspace").is_file());
+}
+
+#[test]
+fn strict_mode_fails_when_a_project_is_skipped() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let root = scratch_dir("broken-project");
+    copy_dir(&fixtures.join("multibin"), &root.join("multibin"));
+    fs::create_dir_all(root.join("broken")).unwrap();
+    fs::write(root.join("broken").join("Cargo.toml"), "[package\n").unwrap();
+
+    let run = |strict: bool| {
+        Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+            .arg("--root")
+            .arg(&root)
+            .args(if strict { &["--strict"][..] } else { &[] })
+            .output()
+            .unwrap()
+    };
+
+    let output = run(true);
+    assert!(!output.status.success());
+    assert!(!root.join("broken-project.code-workspace").exists());
+
+    let output = run(false);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "tool failed: {}", stderr);
+    assert!(stderr.contains("Skipped 1 of 2 projects"), "{}", stderr);
+    assert!(root.join("broken-project.code-workspace").exists());
+}
+
+#[test]
+fn proc_macro_package_gets_no_binary_or_example_configurations() {
+    let root = copy_fixture("procmacro");
+    let workspace = generate_workspace(&root, &[]);
+
+    let names = configuration_names(&workspace);
+    assert!(names.iter().all(|name| !name.starts_with("Debug binary") && !name.starts_with("Debug example")), "{:?}", names);
+    assert!(names.contains(&"Debug integration test 'procmacro::expand'".to_string()), "{:?}", names);
+    assert_eq!(workspace["folders"], serde_json::json!([{ "path": ".", "name": "procmacro" }]));
+}
+
+#[test]
+fn validate_accepts_generated_workspaces_and_rejects_broken_ones() {
+    let root = copy_fixture("workspace");
+    generate_workspace(&root, &["--validate", "--pre-launch-build", "--compound-all", "--with-attach", "--profiles", "dev,release"]);
+
+    fs::write(
+        root.join("workspace.code-workspace"),
+        r#"{ "folders": [], "launch": { "version": "0.2.0", "configurations": [{ "name": "Mine", "type": "lldb", "request": "lunch" }] } }"#,
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+        .arg("--root")
+        .arg(&root)
+        .arg("--validate")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("configuration 'Mine' has a"), "{}", stderr);
+}
+
+#[test]
+fn config_file_supplies_defaults_that_flags_override() {
+    let root = copy_fixture("standalone");
+    fs::write(
+        root.join(".rust-vscode.toml"),
+        "profiles = [\"dev\", \"release\"]\nfeatures = [\"extra\"]\n\n[env]\nRUST_LOG = \"info\"\nMODE = \"file\"\n",
+    )
+    .unwrap();
+
+    let workspace = generate_workspace(&root, &["--env", "MODE=cli"]);
+
+    let config = configuration(&workspace, "Debug binary 'standalone::standalone' (release)");
+    assert_eq!(config["env"], serde_json::json!({ "MODE": "cli", "RUST_LOG": "info" }));
+    assert!(config["cargo"]["args"].as_array().unwrap().contains(&"--features=extra".into()), "{}", config);
+
+    let workspace = generate_workspace(&root, &["--profiles", "dev"]);
+    assert!(configuration_names(&workspace).iter().all(|name| !name.ends_with("(release)")));
+}
+
+#[test]
+fn template_renders_a_configuration_per_runnable() {
+    let root = copy_fixture("standalone");
+    let template = root.join("launch-template.json");
+    fs::write(
+        &template,
+        r#"{
+            // Comments are allowed
+            "name": "Run {{name}}",
+            "type": "lldb",
+            "request": "launch",
+            "cwd": "{{cwd}}",
+            "cargo": { "args": ["build", "--manifest-path={{manifest_path}}", "--{{kind}}={{bin}}"] },
+            "custom": "{{package}}",
+        }"#,
+    )
+    .unwrap();
+
+    let workspace = generate_workspace(&root, &["--template", template.to_str().unwrap()]);
+
+    let config = configuration(&workspace, "Run standalone::standalone");
+    assert_eq!(config["cargo"]["args"][2], "--bin=standalone");
+    assert_eq!(config["cwd"], "${workspaceFolder}");
+    assert_eq!(config["custom"], "standalone");
+    assert!(configuration_names(&workspace).iter().all(|name| name.starts_with("Run ")));
+
+    // Settings the template can't honour are pointed out
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+        .args(["--stdout", "--with-attach", "--template", template.to_str().unwrap()])
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--with-attach has no effect"));
+
+    fs::write(&template, r#"{ "name": "{{unknown}}", "type": "lldb", "request": "launch", "cwd": "." }"#).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+        .args(["--stdout", "--template", template.to_str().unwrap()])
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown placeholder"));
+}
+
+#[test]
+fn cached_metadata_is_reused_until_the_project_changes() {
+    let root = copy_fixture("standalone");
+    // Keep the test's cache out of the real user cache
+    let cache_home = root.parent().unwrap().join("cache");
+    let run = |extra_args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+            .args(["--stdout", "--verbose"])
+            .args(extra_args)
+            .current_dir(&root)
+            .env("XDG_CACHE_HOME", &cache_home)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        let workspace: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        (configuration_names(&workspace), String::from_utf8_lossy(&output.stderr).contains("Reused cached metadata"))
+    };
+
+    let (names, cached) = run(&[]);
+    assert!(!cached);
+    assert_eq!(run(&[]), (names.clone(), true));
+    assert_eq!(run(&["--no-cache"]), (names, false));
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let cache_dir = fs::metadata(cache_home.join("rust-vscode-workspace-configurator")).unwrap();
+        assert_eq!(cache_dir.permissions().mode() & 0o777, 0o700);
+    }
+
+    // A new target file changes its directory's modification time
+    fs::create_dir_all(root.join("examples")).unwrap();
+    fs::write(root.join("examples/added.rs"), "fn main() {}\n").unwrap();
+    let (names, cached) = run(&[]);
+    assert!(!cached);
+    assert!(names.contains(&"Debug example 'standalone::added (example)'".to_string()), "{:?}", names);
+}
+
+#[test]
+fn read_only_workspace_file_is_reported_without_leaving_a_backup() {
+    let root = copy_fixture("standalone");
+    let run = || {
+        Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+            .arg("--root")
+            .arg(&root)
+            .arg("--quiet")
+            .output()
+            .unwrap()
+    };
+    assert!(run().status.success());
+    let workspace_path = root.join("standalone.code-workspace");
+    let mut permissions = fs::metadata(&workspace_path).unwrap().permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(&workspace_path, permissions).unwrap();
+
+    let output = run();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("Cannot write to {}: permission denied", workspace_path.display())), "{}", stderr);
+    let backups: Vec<_> = fs::read_dir(&root)
+        .unwrap()
+        .filter_map(|entry| entry.unwrap().file_name().into_string().ok())
+        .filter(|name| name.contains(".backup"))
+        .collect();
+    assert!(backups.is_empty(), "{:?}", backups);
+}
+
+#[test]
+fn existing_workspace_name_is_kept_unless_name_is_given() {
+    let root = copy_fixture("standalone");
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+            .arg("--root")
+            .arg(&root)
+            .arg("--quiet")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+        let workspace_path = root.join("standalone.code-workspace");
+        let workspace: serde_json::Value = serde_json::from_str(&fs::read_to_string(workspace_path).unwrap()).unwrap();
+        workspace["name"].as_str().unwrap().to_string()
+    };
+
+    assert_eq!(run(&[]), "standalone (Rust)");
+    assert_eq!(run(&["--name", "My tools"]), "My tools");
+    assert_eq!(run(&[]), "My tools");
+
+    // A name typed into the file by hand is just as much the user's choice
+    fs::write(root.join("standalone.code-workspace"), r#"{ "name": "Hand picked", "folders": [] }"#).unwrap();
+    assert_eq!(run(&[]), "Hand picked");
+}
+
+#[test]
+fn unit_tests_of_lib_and_bin_targets_get_their_own_configurations() {
+    let root = copy_fixture("multibin");
+    let workspace = generate_workspace(&root, &[]);
+
+    let config = configuration(&workspace, "Debug unit tests 'multibin::tool (bin)'");
+    let cargo_args = config["cargo"]["args"].as_array().unwrap();
+    assert!(cargo_args.contains(&"--no-run".into()) && cargo_args.contains(&"--bin=tool".into()), "{}", config);
+    assert_eq!(config["cargo"]["filter"], serde_json::json!({ "name": "tool", "kind": "bin" }));
+    assert_eq!(config["args"], serde_json::json!([]));
+}
+
+#[test]
+fn integration_tests_build_their_own_test_target() {
+    let root = copy_fixture("procmacro");
+    let workspace = generate_workspace(&root, &[]);
+
+    let config = configuration(&workspace, "Debug integration test 'procmacro::expand'");
+    assert_eq!(
+        config["cargo"]["args"],
+        serde_json::json!(["test", "--no-run", "--package=procmacro", "--test=expand", "--manifest-path=${workspaceFolder}/Cargo.toml"]),
+    );
+    assert_eq!(config["cargo"]["filter"], serde_json::json!({ "name": "expand", "kind": "test" }));
+}
+
+#[test]
+fn packages_with_a_library_get_a_doctest_task() {
+    let task_labels = |workspace: &serde_json::Value| -> Vec<String> {
+        workspace["tasks"]["tasks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|task| task["label"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let workspace = generate_workspace(&copy_fixture("procmacro"), &[]);
+    let labels = task_labels(&workspace);
+    assert!(labels.contains(&"cargo test --doc (procmacro)".to_string()), "{:?}", labels);
+
+    let workspace = generate_workspace(&copy_fixture("multibin"), &[]);
+    let labels = task_labels(&workspace);
+    assert!(labels.iter().all(|label| !label.contains("--doc")), "{:?}", labels);
+}
+
+#[test]
+fn check_clippy_and_fmt_tasks_report_problems() {
+    let workspace = generate_workspace(&copy_fixture("multibin"), &[]);
+    let tasks = workspace["tasks"]["tasks"].as_array().unwrap();
+    let task = |label: &str| tasks.iter().find(|task| task["label"] == label).unwrap_or_else(|| panic!("no task {}", label));
+
+    for (label, group) in [("cargo check (multibin)", "build"), ("cargo clippy (multibin)", "build"), ("cargo fmt --check (multibin)", "test")] {
+        assert_eq!(task(label)["group"], group);
+        assert_eq!(task(label)["problemMatcher"], "$rustc");
+    }
+    let fmt_args = task("cargo fmt --check (multibin)")["args"].as_array().unwrap();
+    assert_eq!(fmt_args[..2], [serde_json::json!("fmt"), serde_json::json!("--check")]);
+}
+
+#[test]
+fn every_project_gets_a_clean_task() {
+    let workspace = generate_workspace(&copy_fixture("multibin"), &[]);
+    let tasks = workspace["tasks"]["tasks"].as_array().unwrap();
+    let clean = tasks.iter().find(|task| task["label"] == "cargo clean (.)").expect("no clean task");
+    assert_eq!(clean["args"], serde_json::json!(["clean", "--manifest-path=${workspaceFolder}/Cargo.toml"]));
+    assert_eq!(clean["group"], "none");
+    assert_eq!(clean["problemMatcher"], serde_json::json!([]));
+}
+
+#[test]
+fn default_build_task_does_not_replace_the_users_own() {
+    let root = copy_fixture("multibin");
+    let workspace = generate_workspace(&root, &[]);
+    let tasks = workspace["tasks"]["tasks"].as_array().unwrap();
+    let build_all = tasks.iter().find(|task| task["label"] == "cargo build (all packages)").expect("no default build task");
+    assert_eq!(build_all["group"], serde_json::json!({ "kind": "build", "isDefault": true }));
+    assert_eq!(build_all["dependsOn"], serde_json::json!(["cargo build (multibin)"]));
+
+    fs::write(
+        root.join("multibin.code-workspace"),
+        r#"{ "folders": [], "tasks": { "version": "2.0.0", "tasks": [{ "label": "make", "type": "shell", "command": "make", "group": { "kind": "build", "isDefault": true } }] } }"#,
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+        .args(["--quiet", "--no-backup"])
+        .current_dir(&root)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+    let workspace: serde_json::Value = serde_json::from_str(&fs::read_to_string(root.join("multibin.code-workspace")).unwrap()).unwrap();
+    let defaults: Vec<&serde_json::Value> = workspace["tasks"]["tasks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|task| task["group"]["isDefault"] == true)
+        .map(|task| &task["label"])
+        .collect();
+    assert_eq!(defaults, ["make"]);
+}
+
+#[test]
+fn watch_regenerates_when_a_binary_is_added() {
+    let root = copy_fixture("standalone");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+        .args(["--watch", "--quiet", "--no-backup"])
+        .current_dir(&root)
+        .spawn()
+        .unwrap();
+    let workspace_path = root.join("standalone.code-workspace");
+    let wait_for = |expected: &str| {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+        while std::time::Instant::now() < deadline {
+            if fs::read_to_string(&workspace_path).is_ok_and(|content| content.contains(expected)) {
+                return true;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        false
+    };
+
+    let written = wait_for("Debug binary 'standalone::standalone'");
+    fs::create_dir_all(root.join("src/bin")).unwrap();
+    fs::write(root.join("src/bin/added.rs"), "fn main() {}\n").unwrap();
+    let regenerated = written && wait_for("Debug binary 'standalone::added'");
+    fs::write(root.join(".rust-vscode.toml"), "debugger = \"gdb\"\n").unwrap();
+    let reconfigured = regenerated && wait_for("\"cppdbg\"");
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(written, "the workspace was not written");
+    assert!(regenerated, "the workspace was not regenerated");
+    assert!(reconfigured, "the workspace was not regenerated after the config file changed");
+}
+
+#[test]
+fn default_members_only_keeps_the_default_members() {
+    let root = copy_fixture("defaultmembers");
+
+    let names = configuration_names(&generate_workspace(&root, &[]));
+    assert!(names.contains(&"Debug binary 'tool::tool'".to_string()), "{:?}", names);
+
+    let names = configuration_names(&generate_workspace(&root, &["--default-members-only"]));
+    assert!(names.contains(&"Debug binary 'app::app'".to_string()), "{:?}", names);
+    assert!(names.iter().all(|name| !name.contains("tool::")), "{:?}", names);
+}
+
+#[test]
+fn ignored_and_hidden_directories_can_be_configured() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let root = scratch_dir("ignore-dirs");
+    copy_dir(&fixtures.join("standalone"), &root.join("dist").join("standalone"));
+    copy_dir(&fixtures.join("multibin"), &root.join(".hidden").join("multibin"));
+    let folders = |args: &[&str]| -> Vec<String> {
+        let workspace = generate_workspace(&root, args);
+        workspace["folders"].as_array().unwrap().iter().map(|f| f["path"].as_str().unwrap().to_string()).collect()
+    };
+
+    assert_eq!(folders(&[]), ["./dist/standalone"]);
+    assert_eq!(folders(&["--ignore-dir", "dist", "--include-hidden"]), ["./.hidden/multibin"]);
+}
+
+#[test]
+fn finding_projects_applies_the_search_options() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let root = scratch_dir("find-projects");
+    copy_dir(&fixtures.join("standalone"), &root.join("apps").join("standalone"));
+    copy_dir(&fixtures.join("multibin"), &root.join("node_modules").join("multibin"));
+    copy_dir(&fixtures.join("multibin"), &root.join("vendor").join("multibin"));
+    let options = |exclude: &[&str]| DiscoveryOptions {
+        respect_gitignore: true,
+        exclude: build_globset(&exclude.iter().map(|glob| Glob::new(glob).unwrap()).collect::<Vec<_>>()).unwrap(),
+        include: build_globset(&[]).unwrap(),
+        max_depth: None,
+        follow_symlinks: false,
+        ignore_dirs: Vec::new(),
+        include_hidden: false,
+        features: DiscoveryFeatures::Default,
+        packages: Vec::new(),
+        default_members_only: false,
+        metadata_cache: None,
+    };
+
+    let mut projects = find_projects(&root, &options(&[])).unwrap();
+    projects.sort();
+    assert_eq!(projects, [root.join("apps").join("standalone"), root.join("vendor").join("multibin")]);
+    assert_eq!(find_projects(&root, &options(&["vendor/**"])).unwrap(), [root.join("apps").join("standalone")]);
+}
+
+#[test]
+fn virtual_workspace_members_appear_exactly_once() {
+    let root = copy_fixture("virtual");
+    let workspace = generate_workspace(&root, &[]);
+
+    assert_eq!(workspace["folders"], serde_json::json!([{ "path": "." }]));
+    let names = configuration_names(&workspace);
+    for expected in ["Debug binary 'client::client'", "Debug binary 'server::server'", "Debug example 'client::demo (example)'"] {
+        assert_eq!(names.iter().filter(|name| *name == expected).count(), 1, "{:?}", names);
+    }
+}
+
+#[test]
+fn backtrace_is_added_to_the_other_variables() {
+    let root = copy_fixture("standalone");
+    let workspace = generate_workspace(&root, &["--backtrace", "full", "--env", "MODE=test"]);
+    let config = configuration(&workspace, "Debug binary 'standalone::standalone'");
+    assert_eq!(config["env"], serde_json::json!({ "MODE": "test", "RUST_BACKTRACE": "full" }));
+
+    let workspace = generate_workspace(&root, &["--backtrace", "full", "--env", "RUST_BACKTRACE=0"]);
+    let config = configuration(&workspace, "Debug binary 'standalone::standalone'");
+    assert_eq!(config["env"], serde_json::json!({ "RUST_BACKTRACE": "0" }));
+}
+
+#[test]
+fn rust_log_defers_to_the_packages_own_filter() {
+    let root = copy_fixture("standalone");
+    let manifest = fs::read_to_string(root.join("Cargo.toml")).unwrap();
+
+    let workspace = generate_workspace(&root, &["--rust-log", "info"]);
+    let config = configuration(&workspace, "Debug binary 'standalone::standalone'");
+    assert_eq!(config["env"], serde_json::json!({ "RUST_LOG": "info" }));
+
+    fs::write(root.join("Cargo.toml"), format!("{}\n[package.metadata.vscode.env]\nRUST_LOG = \"debug\"\n", manifest)).unwrap();
+    let workspace = generate_workspace(&root, &["--rust-log", "info"]);
+    let config = configuration(&workspace, "Debug binary 'standalone::standalone'");
+    assert_eq!(config["env"], serde_json::json!({ "RUST_LOG": "debug" }));
+}
+
+#[test]
+fn env_is_only_written_when_there_are_variables() {
+    let workspace = generate_workspace(&copy_fixture("standalone"), &[]);
+    let config = configuration(&workspace, "Debug binary 'standalone::standalone'");
+    assert!(config.get("env").is_none(), "{}", config);
+
+    // The fixture's `bevy` is a local stand-in for the real crate
+    let workspace = generate_workspace(&copy_fixture("bevyapp"), &[]);
+    let config = configuration(&workspace, "Debug binary 'bevyapp::bevyapp'");
+    assert_eq!(config["env"], serde_json::json!({ "BEVY_ASSET_ROOT": "${workspaceFolder}" }));
+}
+
+/// Compares `actual` with the file `tests/snapshots/<name>`. Run the tests with
+/// `UPDATE_SNAPSHOTS=1` to write the current output as the new snapshot.
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("snapshots").join(name);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|e| panic!("no snapshot {} ({}); run with UPDATE_SNAPSHOTS=1 to create it", path.display(), e));
+    assert!(expected == actual, "{} no longer matches; run with UPDATE_SNAPSHOTS=1 to accept the new output:\n{}", name, actual);
+}
+
+#[test]
+fn generated_workspace_files_match_their_snapshots() {
+    for fixture in ["standalone", "multibin", "examples", "workspace"] {
+        let root = copy_fixture(fixture);
+        let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+            .args(["--quiet", "--debugger", "lldb"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+
+        let workspace_file = format!("{}.code-workspace", fixture);
+        assert_snapshot(&workspace_file, &fs::read_to_string(root.join(&workspace_file)).unwrap());
+    }
+}
+
+fn runnable(name: &str, runnable_type: RunnableType, package_binary_count: usize, required_features: &[&str]) -> Runnable {
+    let package_path = PathBuf::from("/work/app");
+    // "app::server (example)" names the target "server"
+    let target_name = name.split_once("::").map_or(name, |(_, target)| target);
+    let target_name = target_name.rsplit_once(" (").map_or(target_name, |(target, _)| target);
+    Runnable {
+        name: name.to_string(),
+        package: "app".to_string(),
+        target_name: target_name.to_string(),
+        runnable_type,
+        required_features: required_features.iter().map(|f| f.to_string()).collect(),
+        project_path: package_path.clone(),
+        package_path,
+        target_directory: PathBuf::from("/work/app/target"),
+        package_binary_count,
+        default_run: None,
+        depends_on_bevy: false,
+        has_doctests: false,
+        build_target: None,
+        overrides: VscodeOverrides::default(),
+    }
+}
+
+fn launch_options() -> LaunchOptions {
+    LaunchOptions {
+        debugger: Debugger::Lldb,
+        config_type: None,
+        gdb_path: None,
+        env: BTreeMap::new(),
+        default_env: BTreeMap::new(),
+        pre_launch_build: false,
+        name_features: false,
+        raw_names: false,
+        program_mode: false,
+        profiles: vec![Profile::Dev],
+        target: None,
+        compound_all: false,
+        program_args: BTreeMap::new(),
+        features: Vec::new(),
+        no_default_features: false,
+        stop_at_entry: false,
+        terminal: None,
+        env_file: None,
+        init_commands: Vec::new(),
+        source_map: BTreeMap::new(),
+        with_attach: false,
+        remote: None,
+        template: None,
+    }
+}
+
+#[test]
+fn launch_configurations_match_their_snapshot() {
+    let runnables = [
+        // Named like its package in a package with several binaries, so it's selected explicitly
+        runnable("app::app", RunnableType::Binary, 2, &[]),
+        runnable("app::server", RunnableType::Binary, 2, &["net", "tls"]),
+        runnable("app::demo (example)", RunnableType::Example, 2, &["net"]),
+    ];
+    let launch = generate_launch_config(&runnables, Path::new("/work"), &launch_options());
+    assert_snapshot("launch_configurations.json", &serde_json::to_string_pretty(&launch).unwrap());
+}
+
+#[test]
+fn finding_no_projects_fails_unless_allowed() {
+    let root = scratch_dir("empty");
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+            .arg("--root")
+            .arg(&root)
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No Rust projects (Cargo.toml files) found"));
+
+    let output = run(&["--allow-empty"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(!root.join("empty.code-workspace").exists());
+}
+
+#[test]
+fn list_prints_runnables_by_kind_without_writing() {
+    let root = copy_fixture("multibin");
+    fs::write(
+        root.join("Cargo.toml"),
+        "[package]\nname = \"multibin\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[features]\ncli = []\n\n[[bin]]\nname = \"tool\"\npath = \"src/bin/tool.rs\"\nrequired-features = [\"cli\"]\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+        .arg("--root")
+        .arg(&root)
+        .arg("--list")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let listing = String::from_utf8_lossy(&output.stdout);
+    assert!(listing.starts_with(".\n  Binaries:\n    multibin::multibin\n    multibin::tool [features: cli]\n  Tests:\n"), "{}", listing);
+    assert!(!root.join("multibin.code-workspace").exists());
+}
+
+#[test]
+fn json_report_describes_each_runnable() {
+    let root = copy_fixture("multibin");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+        .arg("--root")
+        .arg(&root)
+        .args(["--report", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let tool = report
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|runnable| runnable["name"] == "multibin::tool")
+        .unwrap();
+    assert_eq!(tool["package"], "multibin");
+    assert_eq!(tool["kind"], "binary");
+    assert_eq!(tool["test_target"], serde_json::Value::Null);
+    assert_eq!(tool["required_features"], serde_json::json!([]));
+    assert_eq!(Path::new(tool["project_path"].as_str().unwrap()), root);
+    let keys: Vec<&String> = tool.as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["name", "package", "kind", "test_target", "harness", "required_features", "project_path"]);
+    assert!(report.as_array().unwrap().iter().any(|runnable| runnable["kind"] == "test" && runnable["test_target"] == "bin"));
+    assert!(!root.join("multibin.code-workspace").exists());
+}
+
+#[test]
+fn gitignore_rules_only_apply_below_their_directory() {
+    let project = copy_fixture("standalone");
+    let root = scratch_dir("tree");
+    copy_dir(&project, &root.join("a").join("standalone"));
+    copy_dir(&project, &root.join("b").join("standalone"));
+    copy_dir(&project, &root.join("c").join("d").join("e").join("standalone"));
+    fs::write(root.join("a").join(".gitignore"), "standalone/\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+        .arg("--root")
+        .arg(&root)
+        .args(["--report", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let mut project_paths: Vec<PathBuf> = report
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|runnable| PathBuf::from(runnable["project_path"].as_str().unwrap()))
+        .collect();
+    project_paths.dedup();
+    assert_eq!(project_paths, [root.join("b").join("standalone"), root.join("c").join("d").join("e").join("standalone")]);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_cycles_are_searched_once() {
+    let project = copy_fixture("standalone");
+    let root = scratch_dir("cyclic");
+    copy_dir(&project, &root.join("crates").join("standalone"));
+    // A link back up to the root, and a second route to the same crate
+    std::os::unix::fs::symlink(&root, root.join("crates").join("up")).unwrap();
+    std::os::unix::fs::symlink(root.join("crates"), root.join("alias")).unwrap();
+
+    for args in [&[][..], &["--follow-symlinks"][..]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+            .arg("--root")
+            .arg(&root)
+            .args(["--report", "json"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let names: Vec<&str> = report.as_array().unwrap().iter().map(|runnable| runnable["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["standalone::standalone", "standalone::standalone (bin test)"], "{:?}", args);
+    }
+}
+
+#[test]
+fn detected_debugger_is_logged_unless_one_is_given() {
+    let root = copy_fixture("standalone");
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+            .arg("--root")
+            .arg(&root)
+            .arg("--stdout")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    assert!(run(&[]).contains("debugger (pass --debugger to change it)"));
+    assert!(!run(&["--debugger", "gdb"]).contains("pass --debugger"));
+}
+
+#[test]
+fn windows_targets_launch_an_exe_whatever_the_debugger() {
+    let root = copy_fixture("multibin");
+    let program = |args: &[&str]| {
+        let workspace = generate_workspace(&root, args);
+        configuration(&workspace, "Debug binary 'multibin::tool'")["program"].as_str().unwrap().to_string()
+    };
+
+    let program_for_gdb = program(&["--debugger", "gdb", "--target", "x86_64-pc-windows-gnu"]);
+    assert!(program_for_gdb.ends_with("/target/x86_64-pc-windows-gnu/debug/tool.exe"), "{}", program_for_gdb);
+    let program_for_lldb_dap = program(&["--debugger", "lldb-dap", "--target", "x86_64-pc-windows-msvc"]);
+    assert!(program_for_lldb_dap.ends_with("/debug/tool.exe"), "{}", program_for_lldb_dap);
+    let program_for_cppvsdbg = program(&["--debugger", "cppvsdbg", "--target", "aarch64-unknown-linux-gnu"]);
+    assert!(program_for_cppvsdbg.ends_with("/debug/tool"), "{}", program_for_cppvsdbg);
+}
+
+#[test]
+fn lldb_dap_configurations_launch_the_built_binary() {
+    let root = copy_fixture("multibin");
+    let workspace = generate_workspace(&root, &["--debugger", "lldb-dap", "--pre-launch-build", "--env", "RUST_LOG=debug"]);
+
+    let config = configuration(&workspace, "Debug binary 'multibin::tool'");
+    assert_eq!(config["type"], "lldb-dap");
+    assert!(config["program"].as_str().unwrap().ends_with("/target/debug/tool"), "{}", config);
+    assert!(config.get("cargo").is_none(), "{}", config);
+    assert_eq!(config["env"], serde_json::json!({ "RUST_LOG": "debug" }));
+    assert_eq!(config["preLaunchTask"], "cargo build --bin=tool (multibin)");
+
+    // Test harnesses have hashed file names, so there's nothing to point `program` at
+    assert!(!configuration_names(&workspace).iter().any(|name| name.starts_with("Debug unit tests ")));
+    assert!(workspace["extensions"]["recommendations"].as_array().unwrap().contains(&"llvm-vs-code-extensions.lldb-dap".into()));
+}
+
+#[test]
+fn program_mode_launches_binaries_without_cargo() {
+    let root = copy_fixture("multibin");
+    let workspace = generate_workspace(&root, &["--program-mode"]);
+
+    let binary = configuration(&workspace, "Debug binary 'multibin::tool'");
+    assert_eq!(binary["type"], "lldb");
+    assert!(binary["program"].as_str().unwrap().ends_with("/target/debug/tool"), "{}", binary);
+    assert!(binary.get("cargo").is_none(), "{}", binary);
+
+    // Test harnesses still need cargo to find them
+    let tests = configuration(&workspace, "Debug unit tests 'multibin::tool (bin)'");
+    assert!(tests.get("program").is_none(), "{}", tests);
+    assert!(tests["cargo"]["args"].as_array().unwrap().contains(&"--no-run".into()), "{}", tests);
+}
+
+/// The task with the given label from a generated workspace.
+fn task<'a>(workspace: &'a serde_json::Value, label: &str) -> &'a serde_json::Value {
+    workspace["tasks"]["tasks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|task| task["label"] == label)
+        .unwrap_or_else(|| panic!("no task labelled {}", label))
+}
+
+#[test]
+fn launched_artifacts_are_built_with_their_own_target_features_and_triple() {
+    let root = copy_fixture("examples");
+    for launch_args in [&["--program-mode"][..], &["--debugger", "gdb"], &["--debugger", "cppvsdbg"], &["--debugger", "lldb-dap"]] {
+        let workspace = generate_workspace(&root, &[launch_args, &["--pre-launch-build", "--validate"]].concat());
+        let example = configuration(&workspace, "Debug example 'demos::basic (example)'");
+        assert_eq!(example["preLaunchTask"], "cargo build --example=basic (demos)", "{:?}", launch_args);
+        let args = &task(&workspace, "cargo build --example=basic (demos)")["args"];
+        assert!(args.as_array().unwrap().contains(&"--example=basic".into()), "{}", args);
+    }
+
+    let root = copy_fixture("multibin");
+    let manifest = fs::read_to_string(root.join("Cargo.toml")).unwrap();
+    let gated = "\n[features]\nextra = []\n\n[[bin]]\nname = \"tool\"\npath = \"src/bin/tool.rs\"\nrequired-features = [\"extra\"]\n";
+    fs::write(root.join("Cargo.toml"), manifest + gated).unwrap();
+    fs::create_dir_all(root.join(".cargo")).unwrap();
+    fs::write(root.join(".cargo").join("config.toml"), "[build]\ntarget = \"thumbv7em-none-eabihf\"\n").unwrap();
+    let workspace = generate_workspace(&root, &["--program-mode", "--pre-launch-build", "--validate"]);
+    let binary = configuration(&workspace, "Debug binary 'multibin::tool'");
+    assert_eq!(binary["preLaunchTask"], "cargo build --bin=tool (multibin)");
+    let args = &task(&workspace, "cargo build --bin=tool (multibin)")["args"];
+    for expected in ["--bin=tool", "--features=extra", "--target=thumbv7em-none-eabihf"] {
+        assert!(args.as_array().unwrap().contains(&expected.into()), "{} missing from {}", expected, args);
+    }
+}
+
+#[test]
+fn name_features_appends_required_features_to_names() {
+    let runnables = [
+        runnable("app::app", RunnableType::Binary, 3, &[]),
+        runnable("app::server", RunnableType::Binary, 3, &["net", "tls"]),
+        runnable("app::kitchen_sink", RunnableType::Binary, 3, &["a", "b", "c", "d", "e"]),
+    ];
+    let options = LaunchOptions { name_features: true, ..launch_options() };
+    let launch = generate_launch_config(&runnables, Path::new("/work"), &options);
+
+    let names: Vec<&str> = launch.configurations.iter().map(|config| config.name.as_str()).collect();
+    assert_eq!(names, [
+        "Debug binary 'app::app'",
+        "Debug binary 'app::kitchen_sink' [a, b, c, +2 more]",
+        "Debug binary 'app::server' [net, tls]",
+    ]);
+}
+
+#[test]
+fn configuration_names_are_normalized_unless_raw_names() {
+    let runnables = [
+        runnable("app::\tserver", RunnableType::Binary, 3, &[]),
+        runnable("app::  server", RunnableType::Binary, 3, &[]),
+    ];
+    let names = |options: &LaunchOptions| -> Vec<String> {
+        generate_launch_config(&runnables, Path::new("/work"), options)
+            .configurations
+            .into_iter()
+            .map(|config| config.name)
+            .collect()
+    };
+
+    // Equal once normalized, so they are told apart like any other collision
+    assert_eq!(names(&launch_options()), ["Debug binary 'app:: server' [./app]", "Debug binary 'app:: server' [./app] #2"]);
+    let raw = LaunchOptions { raw_names: true, ..launch_options() };
+    assert_eq!(names(&raw), ["Debug binary 'app::\tserver'", "Debug binary 'app::  server'"]);
+}
+
+#[test]
+fn cargo_config_build_target_applies_unless_target_is_given() {
+    let root = copy_fixture("multibin");
+    fs::create_dir_all(root.join(".cargo")).unwrap();
+    fs::write(root.join(".cargo").join("config.toml"), "[build]\ntarget = \"thumbv7em-none-eabihf\"\n").unwrap();
+
+    let workspace = generate_workspace(&root, &[]);
+    let cargo_args = &configuration(&workspace, "Debug binary 'multibin::tool'")["cargo"]["args"];
+    assert!(cargo_args.as_array().unwrap().contains(&"--target=thumbv7em-none-eabihf".into()), "{}", cargo_args);
+
+    let workspace = generate_workspace(&root, &["--debugger", "gdb"]);
+    let program = configuration(&workspace, "Debug binary 'multibin::tool'")["program"].as_str().unwrap().to_string();
+    assert!(program.ends_with("/target/thumbv7em-none-eabihf/debug/tool"), "{}", program);
+
+    let workspace = generate_workspace(&root, &["--target", "aarch64-unknown-linux-gnu"]);
+    let cargo_args = &configuration(&workspace, "Debug binary 'multibin::tool'")["cargo"]["args"];
+    assert!(cargo_args.as_array().unwrap().contains(&"--target=aarch64-unknown-linux-gnu".into()), "{}", cargo_args);
+    assert!(!cargo_args.as_array().unwrap().contains(&"--target=thumbv7em-none-eabihf".into()), "{}", cargo_args);
+
+    // Like cargo, the legacy file wins over config.toml in the same directory
+    fs::write(root.join(".cargo").join("config"), "[build]\ntarget = \"riscv32imac-unknown-none-elf\"\n").unwrap();
+    let workspace = generate_workspace(&root, &[]);
+    let cargo_args = &configuration(&workspace, "Debug binary 'multibin::tool'")["cargo"]["args"];
+    assert!(cargo_args.as_array().unwrap().contains(&"--target=riscv32imac-unknown-none-elf".into()), "{}", cargo_args);
+
+    // ...and the environment wins over both
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+        .arg("--root")
+        .arg(&root)
+        .arg("--stdout")
+        .env("CARGO_BUILD_TARGET", "x86_64-unknown-linux-musl")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+    let workspace: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let cargo_args = &configuration(&workspace, "Debug binary 'multibin::tool'")["cargo"]["args"];
+    assert!(cargo_args.as_array().unwrap().contains(&"--target=x86_64-unknown-linux-musl".into()), "{}", cargo_args);
+}
+
+#[test]
+fn cargo_arguments_use_the_target_name_rather_than_the_display_name() {
+    let runnables = [
+        Runnable { target_name: "gen::v2".to_string(), ..runnable("app::gen::v2", RunnableType::Binary, 2, &[]) },
+        Runnable { target_name: "demo (old)".to_string(), ..runnable("app::demo (old) (example)", RunnableType::Example, 2, &[]) },
+        Runnable {
+            target_name: "it (slow)".to_string(),
+            ..runnable("app::it (slow) (test)", RunnableType::Test(TestTarget::Integration), 2, &[])
+        },
+    ];
+    let launch = generate_launch_config(&runnables, Path::new("/work"), &launch_options());
+
+    let cargo_args = |name: &str| -> Vec<String> {
+        let config = launch.configurations.iter().find(|config| config.name == name).unwrap();
+        config.cargo.as_ref().unwrap().args.clone()
+    };
+    assert!(cargo_args("Debug binary 'app::gen::v2'").contains(&"--bin=gen::v2".to_string()));
+    assert!(cargo_args("Debug example 'app::demo (old) (example)'").contains(&"--example=demo (old)".to_string()));
+    assert!(cargo_args("Debug integration test 'app::it (slow)'").contains(&"--test=it (slow)".to_string()));
+}
+
+#[test]
+fn skipped_test_configurations_are_summed_up_in_one_warning() {
+    let root = copy_fixture("multibin");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+        .arg("--root")
+        .arg(&root)
+        .args(["--stdout", "--debugger", "gdb", "--profiles", "dev,release"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let warnings: Vec<&str> = stderr.lines().filter(|line| line.contains("Skipp")).collect();
+    assert_eq!(warnings.len(), 1, "{}", stderr);
+    assert!(warnings[0].contains("Skipped 4 test and bench configurations"), "{}", stderr);
+}
+
+#[test]
+fn gdb_is_resolved_from_path_unless_one_is_named() {
+    let root = copy_fixture("standalone");
+    let debugger_path = |args: &[&str]| {
+        let workspace = generate_workspace(&root, &[&["--debugger", "gdb"], args].concat());
+        configuration(&workspace, "Debug binary 'standalone::standalone'")["miDebuggerPath"].clone()
+    };
+
+    assert_eq!(debugger_path(&[]), "gdb");
+    assert_eq!(debugger_path(&["--gdb-path", "arm-none-eabi-gdb"]), "arm-none-eabi-gdb");
+}
+
+#[test]
+fn printed_workspace_is_plain_json_even_when_the_file_has_comments() {
+    let root = copy_fixture("standalone");
+    let workspace_path = root.join("standalone.code-workspace");
+    let original = "{\n  // my folders\n  \"folders\": [],\n  \"settings\": { \"editor.formatOnSave\": true, },\n}\n";
+    fs::write(&workspace_path, original).unwrap();
+
+    // generate_workspace parses stdout as strict JSON
+    let workspace = generate_workspace(&root, &[]);
+    assert_eq!(workspace["settings"]["editor.formatOnSave"], true);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+        .arg("--root")
+        .arg(&root)
+        .arg("--dry-run")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("// my folders"));
+    assert_eq!(fs::read_to_string(&workspace_path).unwrap(), original);
+}
+
+#[test]
+fn package_filter_is_checked_across_all_roots() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let parent = scratch_dir("package-roots");
+    copy_dir(&fixtures.join("multibin"), &parent.join("tools").join("multibin"));
+    copy_dir(&fixtures.join("defaultrun"), &parent.join("services").join("defaultrun"));
+    let warnings = |package: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rust-vscode-workspace-configurator"))
+            .arg("--root")
+            .arg(parent.join("tools"))
+            .arg("--root")
+            .arg(parent.join("services"))
+            .args(["--package", package, "--stdout"])
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "tool failed: {}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stderr).matches("did not match any discovered package").count()
+    };
+
+    assert_eq!(warnings("multibin"), 0);
+    assert_eq!(warnings("nonexistent"), 1);
+}
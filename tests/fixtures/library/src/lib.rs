@@ -0,0 +1 @@
+pub fn answer() -> u32 { 42 }